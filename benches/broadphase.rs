@@ -0,0 +1,96 @@
+//! Compares the collision pass of the game, with and without the spatial hash,
+//! as the number of entities grows.
+//!
+//! Run it with `cargo bench --bench broadphase`.
+
+extern crate my_rust_game;
+extern crate rand;
+
+use my_rust_game::phi::broadphase::SpatialHash;
+use my_rust_game::phi::data::{Rectangle, seeded_rng};
+use rand::Rng;
+use std::time::Instant;
+
+/// The size of the field over which the entities are spread.
+const FIELD_W: f64 = 8000.0;
+const FIELD_H: f64 = 6000.0;
+
+const ASTEROID_SIDE: f64 = 96.0;
+const BULLET_W: f64 = 8.0;
+const BULLET_H: f64 = 4.0;
+
+/// How many times each pass is run, to smooth out the measurements.
+const ROUNDS: u32 = 10;
+
+fn spread(count: usize, w: f64, h: f64, seed: u64) -> Vec<Rectangle> {
+    let mut rng = seeded_rng(seed);
+    (0..count)
+        .map(|_| Rectangle {
+            x: rng.gen::<f64>() * (FIELD_W - w),
+            y: rng.gen::<f64>() * (FIELD_H - h),
+            w: w,
+            h: h,
+        })
+        .collect()
+}
+
+/// Every asteroid against every bullet, as the game used to do.
+fn naive(asteroids: &[Rectangle], bullets: &[Rectangle]) -> usize {
+    let mut hits = 0;
+    for asteroid in asteroids {
+        for bullet in bullets {
+            if asteroid.overlaps(*bullet) {
+                hits += 1;
+            }
+        }
+    }
+    hits
+}
+
+/// The asteroids and bullets are registered, and only the pairs which share
+/// a cell are looked at.
+fn hashed(grid: &mut SpatialHash<usize>, asteroids: &[Rectangle], bullets: &[Rectangle]) -> usize {
+    grid.clear();
+    for (i, rect) in asteroids.iter().chain(bullets).enumerate() {
+        grid.insert(i, *rect);
+    }
+
+    //? The asteroids were registered first, so they come first in the pairs
+    //? which they share with a bullet.
+    grid.pairs().into_iter()
+        .filter(|&(a, b)| a < asteroids.len() && b >= asteroids.len())
+        .count()
+}
+
+/// The average time taken by `pass`, in milliseconds, and what it returned.
+fn time<F: FnMut() -> usize>(mut pass: F) -> (f64, usize) {
+    let start = Instant::now();
+    let mut hits = 0;
+    for _ in 0..ROUNDS {
+        hits = pass();
+    }
+
+    let elapsed = start.elapsed();
+    let ms = elapsed.as_secs() as f64 * 1_000.0 + elapsed.subsec_nanos() as f64 / 1_000_000.0;
+    (ms / ROUNDS as f64, hits)
+}
+
+fn main() {
+    println!("{:>9} {:>9} {:>12} {:>12} {:>9}", "asteroids", "bullets", "naive (ms)", "hashed (ms)", "speedup");
+
+    let mut grid = SpatialHash::new(ASTEROID_SIDE);
+
+    for &count in &[100, 500, 1_000, 2_000, 5_000, 10_000] {
+        let asteroids = spread(count, ASTEROID_SIDE, ASTEROID_SIDE, 1);
+        let bullets = spread(count, BULLET_W, BULLET_H, 2);
+
+        let (naive_ms, naive_hits) = time(|| naive(&asteroids, &bullets));
+        let (hashed_ms, hashed_hits) = time(|| hashed(&mut grid, &asteroids, &bullets));
+
+        // Both passes must find the same collisions.
+        assert_eq!(naive_hits, hashed_hits);
+
+        println!("{:>9} {:>9} {:>12.3} {:>12.3} {:>8.1}x",
+                 count, count, naive_ms, hashed_ms, naive_ms / hashed_ms);
+    }
+}
@@ -0,0 +1,11 @@
+//! The engine of the game, `phi`, as a library, so that the benchmarks and
+//! any other tool may use it through its public API.
+
+extern crate find_folder;
+extern crate rand;
+extern crate sdl2;
+extern crate sdl2_image;
+extern crate sdl2_ttf;
+extern crate sdl2_mixer;
+
+pub mod phi;
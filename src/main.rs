@@ -1,15 +1,66 @@
+extern crate my_rust_game;
 extern crate rand;
 extern crate sdl2;
-extern crate sdl2_image;
-extern crate sdl2_ttf;
 extern crate sdl2_mixer;
 
-mod phi;
+//? The engine is built as a library, which the game refers to as `phi`.
+use my_rust_game::phi;
+
 mod views;
 
 
+/// Reads the settings of the game loop from the command line. Every flag is
+/// followed by its value, e.g. `--seed 42`.
+fn config_from_args() -> ::phi::Config {
+    let mut config = ::phi::Config::default();
+    let mut args = ::std::env::args().skip(1);
+
+    while let Some(flag) = args.next() {
+        let value = args.next().unwrap_or(String::new());
+
+        match flag.as_str() {
+            "--seed" => match value.parse() {
+                Ok(seed) => config.seed = Some(seed),
+                Err(_) => println!("Invalid seed `{}`, picking one at random", value),
+            },
+
+            "--tick-rate" => match value.parse::<f64>() {
+                Ok(rate) if rate.is_finite() && rate > 0.0 => config.tick_rate = rate,
+                _ => println!("Invalid tick rate `{}`, using {}", value, config.tick_rate),
+            },
+
+            "--lives" => match value.parse() {
+                Ok(lives) if lives > 0 => config.lives = lives,
+                _ => println!("Invalid number of lives `{}`, using {}", value, config.lives),
+            },
+
+            "--resolution" => match ::phi::replay::parse_resolution(&value) {
+                Some(resolution) => config.resolution = resolution,
+                None => println!("Invalid resolution `{}`, using {}x{}",
+                                 value, config.resolution.0, config.resolution.1),
+            },
+
+            "--record" => config.record = Some(value.into()),
+
+            "--replay" => config.replay = Some(value.into()),
+
+            "--controls" => config.controls = Some(value.into()),
+
+            "--assets" => config.assets = Some(value.into()),
+
+            _ => println!("Ignoring unknown flag `{}`", flag),
+        }
+    }
+
+    config
+}
+
 fn main() {
-    ::phi::spawn("射击游戏", |phi| {
-        Box::new(::views::main_menu::MainMenuView::new(phi))
+    ::phi::spawn("射击游戏", config_from_args(), |phi| {
+        // Report every missing asset at once, before anything is shown.
+        phi.assets.check(&phi.renderer, &::views::manifest::required())?;
+
+        ::views::main_menu::MainMenuView::new(phi)
+            .map(|view| Box::new(view) as Box<::phi::View>)
     });
-}
\ No newline at end of file
+}
@@ -0,0 +1,272 @@
+//! Loading of the files which the game is made of.
+//!
+//! Assets are named by their path relative to the assets directory, e.g.
+//! `"spaceship.png"`, so that the game finds them whichever directory it is
+//! started from.
+//!
+//! Every file is only loaded once: asking for it again returns a handle to
+//! the same texture, sound or font. When a file cannot be loaded, the error
+//! names it, so that the player can be told what is wrong with their
+//! installation.
+
+use phi::data::Rectangle;
+use phi::gfx::{AnimatedSpriteDescr, Sprite};
+use phi::shapes::Mask;
+use phi::sheet::Spritesheet;
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::render::Renderer;
+use sdl2::surface::Surface;
+use sdl2_image::LoadSurface;
+use sdl2_mixer::{Chunk, Music};
+use sdl2_ttf::{Font, Sdl2TtfContext};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// Why an asset could not be loaded.
+#[derive(Clone, Debug)]
+pub enum AssetError {
+    /// There is no file at this path.
+    Missing(PathBuf),
+
+    /// The file exists, but could not be understood.
+    Corrupt(PathBuf, String),
+
+    /// Several assets could not be loaded.
+    Several(Vec<AssetError>),
+}
+
+impl AssetError {
+    /// Tells whether the file at `path` is missing, or why it could not be
+    /// loaded otherwise.
+    pub fn at(path: &Path, reason: String) -> AssetError {
+        if path.exists() {
+            AssetError::Corrupt(path.to_path_buf(), reason)
+        } else {
+            AssetError::Missing(path.to_path_buf())
+        }
+    }
+}
+
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AssetError::Missing(ref path) =>
+                write!(f, "missing file {}", path.display()),
+            AssetError::Corrupt(ref path, ref reason) =>
+                write!(f, "could not load {}: {}", path.display(), reason),
+            AssetError::Several(ref errors) => {
+                // One error per line
+                let lines: Vec<_> = errors.iter().map(|e| e.to_string()).collect();
+                write!(f, "{}", lines.join("\n"))
+            },
+        }
+    }
+}
+
+/// An asset which the game cannot do without, and what it is used as.
+pub enum Required {
+    Image(&'static str),
+
+    /// The description of a spritesheet, whose frames must all lie inside of
+    /// its image.
+    Spritesheet(&'static str),
+
+    Sound(&'static str),
+    Music(&'static str),
+    Font(&'static str),
+}
+
+/// Keeps every asset which was loaded so far.
+pub struct Assets {
+    /// The directory against which the paths of the assets are resolved.
+    root: PathBuf,
+    ttf_context: Sdl2TtfContext,
+    sprites: HashMap<String, Sprite>,
+    sheets: HashMap<String, Rc<Spritesheet>>,
+    masks: HashMap<String, Vec<Rc<Mask>>>,
+    chunks: HashMap<String, Rc<Chunk>>,
+    music: HashMap<String, Rc<Music>>,
+    fonts: HashMap<(String, i32), Rc<Font>>,
+}
+
+impl Assets {
+    pub fn new(ttf_context: Sdl2TtfContext, root: PathBuf) -> Assets {
+        Assets {
+            root: root,
+            ttf_context: ttf_context,
+            sprites: HashMap::new(),
+            sheets: HashMap::new(),
+            masks: HashMap::new(),
+            chunks: HashMap::new(),
+            music: HashMap::new(),
+            fonts: HashMap::new(),
+        }
+    }
+
+    /// Where the asset named `path` is found on disk.
+    pub fn path(&self, path: &str) -> PathBuf {
+        self.root.join(path)
+    }
+
+    /// Loads every asset listed in `manifest`, so that all of the missing or
+    /// damaged files are reported at once rather than whenever the game first
+    /// needs them.
+    pub fn check(&mut self, renderer: &Renderer, manifest: &[Required]) -> Result<(), AssetError> {
+        let mut errors = vec![];
+
+        for required in manifest {
+            let result = match *required {
+                Required::Image(path) => self.sprite(renderer, path).map(|_| ()),
+                Required::Spritesheet(path) => self.sheet(path).and_then(|sheet| {
+                    let descr = sheet.descr();
+                    let image = self.sprite(renderer, descr.image_path)?;
+                    descr.check_fits(image.size())
+                        .map_err(|e| AssetError::Corrupt(self.path(descr.image_path), e))
+                }),
+                Required::Sound(path) => self.chunk(path).map(|_| ()),
+                Required::Music(path) => self.music(path).map(|_| ()),
+                //? The size does not matter, as long as the file can be read.
+                Required::Font(path) => self.font(path, 12).map(|_| ()),
+            };
+
+            if let Err(e) = result {
+                errors.push(e);
+            }
+        }
+
+        match errors.len() {
+            0 => Ok(()),
+            1 => Err(errors.remove(0)),
+            _ => Err(AssetError::Several(errors)),
+        }
+    }
+
+    /// The whole image at `path`.
+    //? Sprites already share their texture when they are cloned, so there is
+    //? no need to wrap them in an `Rc`.
+    pub fn sprite(&mut self, renderer: &Renderer, path: &str) -> Result<Sprite, AssetError> {
+        if let Some(sprite) = self.sprites.get(path) {
+            return Ok(sprite.clone());
+        }
+
+        let sprite = Sprite::load(renderer, &self.path(path))?;
+
+        self.sprites.insert(path.to_string(), sprite.clone());
+        Ok(sprite)
+    }
+
+    /// The description of the spritesheet at `path`. Its image is loaded
+    /// separately, through `sprite`.
+    pub fn sheet(&mut self, path: &str) -> Result<Rc<Spritesheet>, AssetError> {
+        if let Some(sheet) = self.sheets.get(path) {
+            return Ok(sheet.clone());
+        }
+
+        let full_path = self.path(path);
+        let mut contents = String::new();
+        File::open(&full_path)
+            .and_then(|mut f| f.read_to_string(&mut contents))
+            .map_err(|e| AssetError::at(&full_path, e.to_string()))?;
+
+        let sheet = Rc::new(Spritesheet::parse(path, &contents)
+            .map_err(|e| AssetError::Corrupt(full_path.clone(), e))?);
+
+        self.sheets.insert(path.to_string(), sheet.clone());
+        Ok(sheet)
+    }
+
+    /// The masks of the solid pixels of every frame described by `descr`, in
+    /// the order of `gfx::load_frames`.
+    pub fn masks(&mut self, descr: &AnimatedSpriteDescr) -> Result<Vec<Rc<Mask>>, AssetError> {
+        if let Some(masks) = self.masks.get(descr.image_path) {
+            return Ok(masks.clone());
+        }
+
+        //? Textures live on the graphics card, so the pixels are read from
+        //? the file once more.
+        let full_path = self.path(descr.image_path);
+        let corrupt = |e: String| AssetError::Corrupt(full_path.clone(), e);
+
+        let surface = Surface::from_file(&full_path)
+            .map_err(|e| AssetError::at(&full_path, e))?
+            .convert_format(PixelFormatEnum::RGBA8888)
+            .map_err(&corrupt)?;
+
+        let (image_w, image_h) = (surface.width() as usize, surface.height() as usize);
+        descr.check_fits((image_w as f64, image_h as f64)).map_err(&corrupt)?;
+
+        // Every pixel is a 32-bit number, whose lowest byte is the alpha.
+        let alpha_byte = if cfg!(target_endian = "little") { 0 } else { 3 };
+        let pitch = surface.pitch() as usize;
+        let alpha: Vec<u8> = surface.with_lock(|pixels| {
+            let mut alpha = Vec::with_capacity(image_w * image_h);
+            for y in 0..image_h {
+                for x in 0..image_w {
+                    alpha.push(pixels[y * pitch + x * 4 + alpha_byte]);
+                }
+            }
+            alpha
+        });
+
+        let mut masks = Vec::with_capacity(descr.total_frames);
+        for i in 0..descr.total_frames {
+            let (xth, yth) = (i % descr.frames_wide, i / descr.frames_wide);
+            masks.push(Rc::new(Mask::from_alpha(&alpha, image_w, Rectangle {
+                w: descr.frame_w,
+                h: descr.frame_h,
+                x: descr.frame_w * xth as f64,
+                y: descr.frame_h * yth as f64,
+            })));
+        }
+
+        self.masks.insert(descr.image_path.to_string(), masks.clone());
+        Ok(masks)
+    }
+
+    /// The sound effect at `path`.
+    pub fn chunk(&mut self, path: &str) -> Result<Rc<Chunk>, AssetError> {
+        if let Some(chunk) = self.chunks.get(path) {
+            return Ok(chunk.clone());
+        }
+
+        let full_path = self.path(path);
+        let chunk = Rc::new(Chunk::from_file(&full_path)
+            .map_err(|e| AssetError::at(&full_path, e.to_string()))?);
+
+        self.chunks.insert(path.to_string(), chunk.clone());
+        Ok(chunk)
+    }
+
+    /// The music at `path`.
+    pub fn music(&mut self, path: &str) -> Result<Rc<Music>, AssetError> {
+        if let Some(music) = self.music.get(path) {
+            return Ok(music.clone());
+        }
+
+        let full_path = self.path(path);
+        let music = Rc::new(Music::from_file(&full_path)
+            .map_err(|e| AssetError::at(&full_path, e.to_string()))?);
+
+        self.music.insert(path.to_string(), music.clone());
+        Ok(music)
+    }
+
+    /// The font at `path`, at the given point size.
+    pub fn font(&mut self, path: &str, size: i32) -> Result<Rc<Font>, AssetError> {
+        let key = (path.to_string(), size);
+        if let Some(font) = self.fonts.get(&key) {
+            return Ok(font.clone());
+        }
+
+        let full_path = self.path(path);
+        let font = Rc::new(self.ttf_context.load_font(&full_path, size as u16)
+            .map_err(|e| AssetError::at(&full_path, e.to_string()))?);
+
+        self.fonts.insert(key, font.clone());
+        Ok(font)
+    }
+}
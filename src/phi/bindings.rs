@@ -0,0 +1,226 @@
+//! Which keys and controller buttons trigger which actions, and how the keys
+//! are saved between sessions.
+//!
+//! Bindings are stored in a text file with one action per line, followed by
+//! the names of the keys bound to it, separated by commas. Lines starting
+//! with `#` are ignored. Actions which are not listed, or listed without any
+//! key, keep their default keys.
+//!
+//! ```text
+//! MoveUp = Up, W
+//! Fire = Space, Left Ctrl
+//! ```
+
+use phi::Action;
+use sdl2::controller::{Axis, Button};
+use sdl2::keyboard::Keycode;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+/// The name of the bindings file inside of the configuration directory.
+pub const CONTROLS_FILE: &'static str = "controls.cfg";
+
+/// Something on a game controller which may be held down to trigger an
+/// action.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PadControl {
+    Button(Button),
+
+    /// A trigger, which counts as held once it is pulled past halfway.
+    Trigger(Axis),
+}
+
+#[derive(Clone)]
+pub struct Bindings {
+    keys: HashMap<Action, Vec<Keycode>>,
+
+    /// The controls of game controllers bound to every action. These always
+    /// keep their defaults.
+    pads: HashMap<Action, Vec<PadControl>>,
+
+    /// Where the bindings are saved, if anywhere.
+    path: Option<PathBuf>,
+}
+
+impl Bindings {
+    /// The bindings used when the player has not chosen any.
+    pub fn defaults() -> Bindings {
+        Bindings {
+            keys: Action::all().into_iter()
+                .map(|action| (action, action.default_keys()))
+                .collect(),
+            pads: Action::all().into_iter()
+                .map(|action| (action, action.default_pads()))
+                .collect(),
+            path: None,
+        }
+    }
+
+    /// Reads the bindings stored at `path`, which is also where they will be
+    /// saved. If the file does not exist yet, then the default bindings are
+    /// used. Invalid lines are reported and skipped.
+    pub fn load(path: &Path) -> Bindings {
+        let mut bindings = Bindings::defaults();
+        bindings.path = Some(path.to_path_buf());
+
+        let mut contents = String::new();
+        if let Err(e) = File::open(path).and_then(|mut f| f.read_to_string(&mut contents)) {
+            if e.kind() != ::std::io::ErrorKind::NotFound {
+                println!("Could not read {}: {}", path.display(), e);
+            }
+            return bindings;
+        }
+
+        for (i, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            match Bindings::parse_line(line) {
+                Ok((action, keys)) => { bindings.keys.insert(action, keys); },
+                Err(e) => println!("{}:{}: {}", path.display(), i + 1, e),
+            }
+        }
+
+        bindings
+    }
+
+    fn parse_line(line: &str) -> Result<(Action, Vec<Keycode>), String> {
+        let mut parts = line.splitn(2, '=');
+        let name = parts.next().unwrap_or("").trim();
+        let keys = parts.next().ok_or(format!("expected `{} = <keys>`", name))?;
+
+        let action = Action::from_name(name)
+            .ok_or(format!("unknown action `{}`", name))?;
+
+        let mut codes = vec![];
+        for key in keys.split(',').map(str::trim).filter(|key| !key.is_empty()) {
+            codes.push(Keycode::from_name(key).ok_or(format!("unknown key `{}`", key))?);
+        }
+
+        //? Just like `toggle`, never leave an action without a key.
+        if codes.is_empty() {
+            return Err(format!("no key for `{}`, keeping the default keys", name));
+        }
+
+        Ok((action, codes))
+    }
+
+    /// Writes the bindings back to the file they were loaded from.
+    pub fn save(&self) -> Result<(), String> {
+        let path = match self.path {
+            Some(ref path) => path,
+            None => return Err("no file to save the bindings to".to_string()),
+        };
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+
+        let mut contents = String::new();
+        for action in Action::all() {
+            let names: Vec<_> = self.keys(action).iter().map(|key| key.name()).collect();
+            contents.push_str(&format!("{} = {}\n", action.name(), names.join(", ")));
+        }
+
+        File::create(path)
+            .and_then(|mut f| f.write_all(contents.as_bytes()))
+            .map_err(|e| format!("could not write {}: {}", path.display(), e))
+    }
+
+    /// The keys bound to `action`.
+    pub fn keys(&self, action: Action) -> &[Keycode] {
+        self.keys.get(&action).map(|keys| &keys[..]).unwrap_or(&[])
+    }
+
+    /// Every action which `key` triggers.
+    pub fn actions_for(&self, key: Keycode) -> Vec<Action> {
+        Action::all().into_iter()
+            .filter(|&action| self.keys(action).contains(&key))
+            .collect()
+    }
+
+    /// The controller buttons and triggers bound to `action`.
+    pub fn pads(&self, action: Action) -> &[PadControl] {
+        self.pads.get(&action).map(|pads| &pads[..]).unwrap_or(&[])
+    }
+
+    /// Every action which `pad` triggers.
+    pub fn actions_for_pad(&self, pad: PadControl) -> Vec<Action> {
+        Action::all().into_iter()
+            .filter(|&action| self.pads(action).contains(&pad))
+            .collect()
+    }
+
+    /// Binds `key` to `action` if it was not already, and unbinds it
+    /// otherwise. An action always keeps at least one key.
+    pub fn toggle(&mut self, action: Action, key: Keycode) {
+        let keys = self.keys.entry(action).or_insert(vec![]);
+
+        if !keys.contains(&key) {
+            keys.push(key);
+        } else if keys.len() > 1 {
+            keys.retain(|&k| k != key);
+        }
+    }
+
+    /// Restores the default keys of `action`.
+    pub fn reset(&mut self, action: Action) {
+        self.keys.insert(action, action.default_keys());
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use phi::Action;
+    use sdl2::keyboard::Keycode;
+    use std::fs::File;
+    use std::io::Write;
+    use super::Bindings;
+
+    #[test]
+    fn lines_name_an_action_and_its_keys() {
+        assert_eq!(Bindings::parse_line("Fire = Space, Left Ctrl").unwrap(),
+                   (Action::Fire, vec![Keycode::Space, Keycode::LCtrl]));
+        assert_eq!(Bindings::parse_line("MoveUp=W").unwrap(), (Action::MoveUp, vec![Keycode::W]));
+
+        assert!(Bindings::parse_line("Fire").is_err());
+        assert!(Bindings::parse_line("Jump = Space").is_err());
+        assert!(Bindings::parse_line("Fire = Space, Nope").is_err());
+    }
+
+    #[test]
+    fn actions_are_never_left_without_a_key() {
+        assert!(Bindings::parse_line("Fire =").is_err());
+        assert!(Bindings::parse_line("Fire = , ,").is_err());
+
+        let path = ::std::env::temp_dir().join("controls-test-empty.cfg");
+        File::create(&path).unwrap().write_all(b"Fire =\nMoveUp = W\n").unwrap();
+
+        let bindings = Bindings::load(&path);
+        assert_eq!(bindings.keys(Action::Fire), &Action::Fire.default_keys()[..]);
+        assert_eq!(bindings.keys(Action::MoveUp), &[Keycode::W]);
+    }
+
+    #[test]
+    fn saved_bindings_load_back() {
+        let path = ::std::env::temp_dir().join("controls-test-round-trip.cfg");
+        let _ = ::std::fs::remove_file(&path);
+
+        let mut bindings = Bindings::load(&path);
+        bindings.toggle(Action::Fire, Keycode::LCtrl);
+        bindings.toggle(Action::MoveUp, Keycode::Up);
+        bindings.save().unwrap();
+
+        let loaded = Bindings::load(&path);
+        for action in Action::all() {
+            assert_eq!(loaded.keys(action), bindings.keys(action));
+        }
+        assert_eq!(loaded.keys(Action::Fire), &[Keycode::Space, Keycode::LCtrl]);
+        assert_eq!(loaded.keys(Action::MoveUp), &[Keycode::W]);
+    }
+}
@@ -0,0 +1,183 @@
+//! A spatial hash, which quickly finds the entities which might collide.
+//!
+//! Entities are registered every frame with the rectangle containing their
+//! shape. They are sorted into square cells, so that finding what a rectangle
+//! might touch only looks at the entities in the cells which it covers,
+//! rather than at every entity in the world. Only the entities which share a
+//! cell are paired up as candidates, which should then be tested with their
+//! actual shapes.
+
+use phi::data::Rectangle;
+use std::collections::HashMap;
+
+pub struct SpatialHash<K> {
+    /// The side of a cell. It should be about the size of the largest
+    /// entities, so that none of them covers too many cells.
+    cell_size: f64,
+
+    /// The entities found in every cell, as indices into `entries`.
+    cells: HashMap<(i64, i64), Vec<usize>>,
+
+    entries: Vec<(K, Rectangle)>,
+}
+
+impl<K: Copy> SpatialHash<K> {
+    pub fn new(cell_size: f64) -> SpatialHash<K> {
+        assert!(cell_size > 0.0);
+
+        SpatialHash {
+            cell_size: cell_size,
+            cells: HashMap::new(),
+            entries: vec![],
+        }
+    }
+
+    /// Forgets every entity, e.g. before registering them again as they have
+    /// moved.
+    pub fn clear(&mut self) {
+        //? The cells are emptied rather than dropped, so that their memory
+        //? is reused during the next frame.
+        for cell in self.cells.values_mut() {
+            cell.clear();
+        }
+        self.entries.clear();
+    }
+
+    /// Registers the entity `key`, whose shape lies inside of `bounds`.
+    pub fn insert(&mut self, key: K, bounds: Rectangle) {
+        let index = self.entries.len();
+        self.entries.push((key, bounds));
+
+        let (left, top, right, bottom) = self.cell_range(bounds);
+        for y in top..bottom + 1 {
+            for x in left..right + 1 {
+                self.cells.entry((x, y)).or_insert_with(Vec::new).push(index);
+            }
+        }
+    }
+
+    /// The entities whose bounds overlap with `bounds`, each of them once.
+    pub fn query(&self, bounds: Rectangle) -> Vec<K> {
+        let mut found = vec![];
+
+        let (left, top, right, bottom) = self.cell_range(bounds);
+        for y in top..bottom + 1 {
+            for x in left..right + 1 {
+                if let Some(cell) = self.cells.get(&(x, y)) {
+                    found.extend_from_slice(cell);
+                }
+            }
+        }
+
+        //? An entity is found in every cell which it covers.
+        found.sort();
+        found.dedup();
+
+        found.into_iter()
+            .filter(|&i| self.entries[i].1.overlaps(bounds))
+            .map(|i| self.entries[i].0)
+            .collect()
+    }
+
+    /// Every pair of registered entities whose bounds overlap, each of them
+    /// once. The entity which was registered first comes first.
+    pub fn pairs(&self) -> Vec<(K, K)> {
+        let mut found = vec![];
+
+        //? The indices in a cell are in the order in which the entities were
+        //? registered, so that `i < j` in every pair.
+        for cell in self.cells.values() {
+            for (n, &i) in cell.iter().enumerate() {
+                for &j in &cell[n + 1..] {
+                    found.push((i, j));
+                }
+            }
+        }
+
+        //? Two entities are paired in every cell which they both cover.
+        found.sort();
+        found.dedup();
+
+        found.into_iter()
+            .filter(|&(i, j)| self.entries[i].1.overlaps(self.entries[j].1))
+            .map(|(i, j)| (self.entries[i].0, self.entries[j].0))
+            .collect()
+    }
+
+    /// The first and last columns and rows of the cells which `bounds`
+    /// covers.
+    fn cell_range(&self, bounds: Rectangle) -> (i64, i64, i64, i64) {
+        let cell = |coord: f64| (coord / self.cell_size).floor() as i64;
+        (cell(bounds.x), cell(bounds.y), cell(bounds.x + bounds.w), cell(bounds.y + bounds.h))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use phi::data::{Rectangle, seeded_rng};
+    use rand::Rng;
+    use super::SpatialHash;
+
+    fn spread(count: usize, side: f64, seed: u64) -> Vec<Rectangle> {
+        let mut rng = seeded_rng(seed);
+        (0..count)
+            .map(|_| Rectangle {
+                x: rng.gen::<f64>() * 1000.0 - 500.0,
+                y: rng.gen::<f64>() * 1000.0 - 500.0,
+                w: rng.gen::<f64>() * side + 1.0,
+                h: rng.gen::<f64>() * side + 1.0,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn pairs_are_those_of_a_naive_scan() {
+        //? Large and small entities, some of which cover many cells.
+        let mut rects = spread(200, 120.0, 1);
+        rects.extend(spread(500, 10.0, 2));
+
+        let mut grid = SpatialHash::new(96.0);
+        for (i, rect) in rects.iter().enumerate() {
+            grid.insert(i, *rect);
+        }
+
+        let mut naive = vec![];
+        for (i, a) in rects.iter().enumerate() {
+            for (j, b) in rects.iter().enumerate().skip(i + 1) {
+                if a.overlaps(*b) {
+                    naive.push((i, j));
+                }
+            }
+        }
+
+        let mut hashed = grid.pairs();
+        hashed.sort();
+        assert!(!naive.is_empty());
+        assert_eq!(naive, hashed);
+    }
+
+    #[test]
+    fn query_finds_the_same_entities_as_a_naive_scan() {
+        let rects = spread(500, 10.0, 2);
+        let target = Rectangle { x: -100.0, y: -100.0, w: 250.0, h: 180.0 };
+
+        let mut grid = SpatialHash::new(96.0);
+        for (i, rect) in rects.iter().enumerate() {
+            grid.insert(i, *rect);
+        }
+
+        let naive: Vec<_> = (0..rects.len()).filter(|&i| rects[i].overlaps(target)).collect();
+        assert!(!naive.is_empty());
+        assert_eq!(naive, grid.query(target));
+    }
+
+    #[test]
+    fn clear_forgets_every_entity() {
+        let mut grid = SpatialHash::new(10.0);
+        grid.insert(1, Rectangle::with_size(5.0, 5.0));
+        grid.clear();
+
+        assert!(grid.query(Rectangle::with_size(5.0, 5.0)).is_empty());
+    }
+}
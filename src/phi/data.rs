@@ -1,4 +1,5 @@
 use sdl2::rect::Rect as SdlRect;
+use rand::{SeedableRng, XorShiftRng};
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Rectangle {
@@ -53,6 +54,12 @@ impl Rectangle {
             ymax >= self.y && ymax <= self.y + self.h
     }
 
+    /// Whether the point `(x, y)` lies inside of the rectangle.
+    pub fn contains_point(&self, point: (f64, f64)) -> bool {
+        point.0 >= self.x && point.0 < self.x + self.w &&
+            point.1 >= self.y && point.1 < self.y + self.h
+    }
+
     pub fn overlaps(&self, other: Rectangle) -> bool {
         self.x < other.x + other.w &&
             self.x + self.w > other.x &&
@@ -87,25 +94,26 @@ impl Rectangle {
         let y = self.y + self.h / 2.0;
         (x, y)
     }
-}
-
-/// Wraps some value and indicates whether it is still _alive_.
-/// This is useful, for example, when iterating over a bunch of objects and
-/// determining whether any one should be kept alive based on the other
-/// entities in the game.
-pub struct MaybeAlive<T> {
-    pub alive: bool,
-    pub value: T,
-}
 
-impl<T> MaybeAlive<T> {
-    /// Checks whether the value is still alive. If this is the case, then
-    /// return `Some(value)`; otherwise, return `None`.
-    pub fn as_option(self) -> Option<T> {
-        if self.alive {
-            Some(self.value)
-        } else {
-            None
+    /// Return the rectangle found a fraction `alpha` of the way between
+    /// `self` and `next`. This is used to draw objects between two updates.
+    pub fn lerp(self, next: Rectangle, alpha: f64) -> Rectangle {
+        Rectangle {
+            x: self.x + (next.x - self.x) * alpha,
+            y: self.y + (next.y - self.y) * alpha,
+            w: self.w + (next.w - self.w) * alpha,
+            h: self.h + (next.h - self.h) * alpha,
         }
     }
-}
\ No newline at end of file
+}
+
+/// Creates a random number generator whose output is entirely determined by
+/// `seed`, so that anything which draws from it can be reproduced.
+pub fn seeded_rng(seed: u64) -> XorShiftRng {
+    let lo = seed as u32;
+    let hi = (seed >> 32) as u32;
+
+    //? A xorshift generator must not be seeded with zeros only, which the
+    //? constants in the last two words guarantee.
+    XorShiftRng::from_seed([lo, hi, lo ^ 0x9E37_79B9, hi ^ 0x7F4A_7C15])
+}
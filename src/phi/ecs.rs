@@ -0,0 +1,427 @@
+//! A small entity-component store, and the systems shared by every game.
+//!
+//! An `Entity` is only a name, handed out by a `Scene`. What an entity is
+//! made of is given by the components attached to it, each kind of which is
+//! kept in its own `Components` table. Systems then go through the entities
+//! which have the components they care about, e.g. `Scene::move_bodies`
+//! moves everything which has both a `Body` and a `Velocity`.
+//!
+//! Games may keep tables of their own components next to a scene, which they
+//! should clear of the entities that the scene despawns.
+
+use phi::data::Rectangle;
+use phi::gfx::{Animation, CopySprite, Sprite, Transform};
+use phi::shapes::{Hitbox, Shape};
+use sdl2::pixels::Color;
+use sdl2::rect::Point;
+use sdl2::render::{BlendMode, Renderer};
+
+/// The name of something in a scene.
+//? Indices are reused once an entity is despawned. The generation tells an
+//? entity apart from those which had the same index before it, so that a
+//? stale name never refers to a newer entity.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Entity {
+    index: u32,
+    generation: u32,
+}
+
+impl Entity {
+    /// The position of the entity in the tables of components.
+    pub fn index(&self) -> usize {
+        self.index as usize
+    }
+}
+
+/// The components of one kind, for every entity which has one.
+pub struct Components<T> {
+    slots: Vec<Option<(Entity, T)>>,
+}
+
+impl<T> Components<T> {
+    pub fn new() -> Components<T> {
+        Components { slots: vec![] }
+    }
+
+    /// Attaches `value` to `entity`, replacing the one it had, if any.
+    pub fn insert(&mut self, entity: Entity, value: T) {
+        while self.slots.len() <= entity.index() {
+            self.slots.push(None);
+        }
+        self.slots[entity.index()] = Some((entity, value));
+    }
+
+    /// Detaches the component of `entity`, and returns it.
+    pub fn remove(&mut self, entity: Entity) -> Option<T> {
+        if self.get(entity).is_none() {
+            return None;
+        }
+        self.slots[entity.index()].take().map(|(_, value)| value)
+    }
+
+    pub fn get(&self, entity: Entity) -> Option<&T> {
+        match self.slots.get(entity.index()) {
+            Some(&Some((owner, ref value))) if owner == entity => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, entity: Entity) -> Option<&mut T> {
+        match self.slots.get_mut(entity.index()) {
+            Some(&mut Some((owner, ref mut value))) if owner == entity => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.get(entity).is_some()
+    }
+
+    /// Every entity with such a component, in the order of their indices.
+    pub fn entities(&self) -> Vec<Entity> {
+        self.iter().map(|(entity, _)| entity).collect()
+    }
+
+    pub fn iter<'a>(&'a self) -> Box<Iterator<Item = (Entity, &'a T)> + 'a> {
+        Box::new(self.slots.iter()
+            .filter_map(|slot| slot.as_ref().map(|&(entity, ref value)| (entity, value))))
+    }
+
+    pub fn iter_mut<'a>(&'a mut self) -> Box<Iterator<Item = (Entity, &'a mut T)> + 'a> {
+        Box::new(self.slots.iter_mut()
+            .filter_map(|slot| slot.as_mut().map(|&mut (entity, ref mut value)| (entity, value))))
+    }
+}
+
+
+/// Where an entity is, and where it was before the last step, so that it may
+/// be drawn in between.
+#[derive(Clone, Copy, Debug)]
+pub struct Body {
+    pub rect: Rectangle,
+    pub prev: Rectangle,
+}
+
+impl Body {
+    pub fn new(rect: Rectangle) -> Body {
+        Body {
+            rect: rect,
+            prev: rect,
+        }
+    }
+
+    /// Where the entity should be drawn, `alpha` of the way through the next
+    /// step.
+    pub fn rect_at(&self, alpha: f64) -> Rectangle {
+        self.prev.lerp(self.rect, alpha)
+    }
+}
+
+/// Pixels traveled every second, horizontally and vertically.
+#[derive(Clone, Copy, Debug)]
+pub struct Velocity(pub f64, pub f64);
+
+/// When an entity should be despawned.
+#[derive(Clone, Copy, Debug)]
+pub enum Lifetime {
+    /// Once its animation is finished.
+    Animation,
+
+    /// Once it is entirely out of the bounds of the scene, grown by this
+    /// margin on every side.
+    InBounds(f64),
+}
+
+/// How an entity is drawn.
+#[derive(Clone, Copy, Debug)]
+pub enum Look {
+    /// One of the frames of the art found at `art` in the list given to
+    /// `Scene::render`. The frame follows the entity's animation, if it has
+    /// one.
+    Frames { art: usize, frame: usize },
+
+    /// A rectangle of a single color.
+    Fill(Color),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Appearance {
+    pub look: Look,
+    pub transform: Transform,
+
+    /// Entities on lower layers are drawn first, under the others.
+    pub layer: i32,
+
+    /// Whether the entity fades away during the second half of its
+    /// animation.
+    pub fade_out: bool,
+}
+
+impl Appearance {
+    pub fn new(look: Look, layer: i32) -> Appearance {
+        Appearance {
+            look: look,
+            transform: Transform::default(),
+            layer: layer,
+            fade_out: false,
+        }
+    }
+}
+
+
+/// Every entity in a game, and the components which the systems below use.
+pub struct Scene {
+    /// The generation of the entity using every index.
+    generations: Vec<u32>,
+    alive: Vec<bool>,
+    /// Indices which are no longer used, and may be given to new entities.
+    free: Vec<u32>,
+
+    pub bodies: Components<Body>,
+    pub velocities: Components<Velocity>,
+    pub animations: Components<Animation>,
+    pub appearances: Components<Appearance>,
+    pub hitboxes: Components<Hitbox>,
+    pub lifetimes: Components<Lifetime>,
+}
+
+impl Scene {
+    pub fn new() -> Scene {
+        Scene {
+            generations: vec![],
+            alive: vec![],
+            free: vec![],
+            bodies: Components::new(),
+            velocities: Components::new(),
+            animations: Components::new(),
+            appearances: Components::new(),
+            hitboxes: Components::new(),
+            lifetimes: Components::new(),
+        }
+    }
+
+    /// Creates an entity without any component.
+    pub fn spawn(&mut self) -> Entity {
+        let index = match self.free.pop() {
+            Some(index) => index,
+            None => {
+                self.generations.push(0);
+                self.alive.push(false);
+                (self.generations.len() - 1) as u32
+            }
+        };
+
+        self.alive[index as usize] = true;
+
+        Entity {
+            index: index,
+            generation: self.generations[index as usize],
+        }
+    }
+
+    /// Removes `entity` and its components from the scene. Returns `false` if
+    /// it was already gone.
+    pub fn despawn(&mut self, entity: Entity) -> bool {
+        if !self.is_alive(entity) {
+            return false;
+        }
+
+        self.bodies.remove(entity);
+        self.velocities.remove(entity);
+        self.animations.remove(entity);
+        self.appearances.remove(entity);
+        self.hitboxes.remove(entity);
+        self.lifetimes.remove(entity);
+
+        self.alive[entity.index()] = false;
+        self.generations[entity.index()] += 1;
+        self.free.push(entity.index);
+        true
+    }
+
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        self.alive.get(entity.index()) == Some(&true) &&
+            self.generations[entity.index()] == entity.generation
+    }
+
+    /// Moves every entity with a `Body` by its `Velocity`.
+    pub fn move_bodies(&mut self, dt: f64) {
+        for (entity, velocity) in self.velocities.iter() {
+            if let Some(body) = self.bodies.get_mut(entity) {
+                body.prev = body.rect;
+                body.rect.x += velocity.0 * dt;
+                body.rect.y += velocity.1 * dt;
+            }
+        }
+    }
+
+    /// Advances every animation, and shows its current frame.
+    pub fn animate(&mut self, dt: f64) {
+        for (entity, anim) in self.animations.iter_mut() {
+            anim.add_time(dt);
+
+            if let Some(appearance) = self.appearances.get_mut(entity) {
+                if let Look::Frames { ref mut frame, .. } = appearance.look {
+                    *frame = anim.current_frame();
+                }
+            }
+        }
+    }
+
+    /// Despawns the entities whose time has come, and returns them so that
+    /// the game may forget about them too.
+    pub fn expire(&mut self, bounds: Rectangle) -> Vec<Entity> {
+        let mut expired = vec![];
+
+        for (entity, lifetime) in self.lifetimes.iter() {
+            let over = match *lifetime {
+                Lifetime::Animation =>
+                    self.animations.get(entity).map(Animation::is_finished).unwrap_or(true),
+                Lifetime::InBounds(margin) => {
+                    let area = Rectangle {
+                        x: bounds.x - margin,
+                        y: bounds.y - margin,
+                        w: bounds.w + 2.0 * margin,
+                        h: bounds.h + 2.0 * margin,
+                    };
+                    self.bodies.get(entity).map(|body| !body.rect.overlaps(area)).unwrap_or(true)
+                },
+            };
+
+            if over {
+                expired.push(entity);
+            }
+        }
+
+        for &entity in &expired {
+            self.despawn(entity);
+        }
+
+        expired
+    }
+
+    /// Draws every entity which has a `Body` and an `Appearance`, layer by
+    /// layer, `alpha` of the way through the next step. The frames of the
+    /// entities are taken from `art`.
+    pub fn render(&self, renderer: &mut Renderer, alpha: f64, art: &[Vec<Sprite>]) {
+        let mut drawn: Vec<_> = self.appearances.iter()
+            .filter_map(|(entity, appearance)| {
+                self.bodies.get(entity).map(|body| (appearance.layer, entity, *appearance, *body))
+            })
+            .collect();
+
+        //? Within a layer, the entities keep the order of their indices.
+        drawn.sort_by_key(|&(layer, entity, _, _)| (layer, entity.index()));
+
+        for (_, entity, appearance, body) in drawn {
+            let rect = body.rect_at(alpha);
+            let mut transform = appearance.transform;
+
+            if appearance.fade_out {
+                if let Some(anim) = self.animations.get(entity) {
+                    let progress = anim.current_frame() as f64 / anim.frames() as f64;
+                    let fade = (2.0 - 2.0 * progress).min(1.0);
+                    transform.alpha = (transform.alpha as f64 * fade) as u8;
+                }
+            }
+
+            match appearance.look {
+                Look::Frames { art: index, frame } => {
+                    if let Some(sprite) = art.get(index).and_then(|frames| frames.get(frame)) {
+                        renderer.copy_sprite_ex(sprite, rect, transform);
+                    }
+                },
+                Look::Fill(color) => {
+                    let (r, g, b) = match color {
+                        Color::RGB(r, g, b) | Color::RGBA(r, g, b, _) => (r, g, b),
+                    };
+                    renderer.set_blend_mode(BlendMode::Blend);
+                    renderer.set_draw_color(Color::RGBA(r, g, b, transform.alpha));
+
+                    if transform.angle == 0.0 {
+                        renderer.fill_rect(rect.to_sdl().unwrap());
+                    } else {
+                        fill_rotated(renderer, rect, transform);
+                    }
+
+                    renderer.set_blend_mode(BlendMode::None);
+                },
+            }
+        }
+    }
+}
+
+/// Fills `rect`, rotated clockwise around its pivot as told by `transform`,
+/// with the current draw color.
+//? SDL can only fill rectangles which are not rotated, so the box is drawn as
+//? a stack of horizontal lines instead, one for every row of pixels.
+fn fill_rotated(renderer: &mut Renderer, rect: Rectangle, transform: Transform) {
+    //? Turning around the pivot is turning around the center, after moving
+    //? the center around the pivot.
+    let (px, py) = transform.pivot_in(rect);
+    let (px, py) = (rect.x + px, rect.y + py);
+    let (cx, cy) = rect.center();
+    let (sin, cos) = transform.angle.to_radians().sin_cos();
+
+    let corners = Shape::Oriented {
+        center: (px + (cx - px) * cos - (cy - py) * sin,
+                 py + (cx - px) * sin + (cy - py) * cos),
+        half_size: (rect.w / 2.0, rect.h / 2.0),
+        angle: transform.angle,
+    }.corners();
+
+    let top = corners.iter().map(|c| c.1).fold(::std::f64::INFINITY, f64::min).floor();
+    let bottom = corners.iter().map(|c| c.1).fold(::std::f64::NEG_INFINITY, f64::max).ceil();
+
+    let mut y = top;
+    while y < bottom {
+        // Where the middle of the row crosses the sides of the box
+        let row = y + 0.5;
+        let mut crossings = vec![];
+
+        for i in 0..corners.len() {
+            let (a, b) = (corners[i], corners[(i + 1) % corners.len()]);
+            if (a.1 <= row) != (b.1 <= row) {
+                crossings.push(a.0 + (row - a.1) / (b.1 - a.1) * (b.0 - a.0));
+            }
+        }
+
+        if crossings.len() >= 2 {
+            let left = crossings.iter().cloned().fold(::std::f64::INFINITY, f64::min);
+            let right = crossings.iter().cloned().fold(::std::f64::NEG_INFINITY, f64::max);
+            renderer.draw_line(Point::new(left.round() as i32, y as i32),
+                               Point::new(right.round() as i32, y as i32)).unwrap();
+        }
+
+        y += 1.0;
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{Components, Scene};
+
+    #[test]
+    fn stale_entities_miss_after_their_slot_is_reused() {
+        let mut scene = Scene::new();
+        let mut names = Components::new();
+
+        let old = scene.spawn();
+        names.insert(old, "old");
+        assert!(scene.despawn(old));
+        assert!(!scene.despawn(old));
+
+        //? The index of `old` is free, so it is given to the next entity.
+        let new = scene.spawn();
+        names.insert(new, "new");
+        assert_eq!(old.index(), new.index());
+
+        assert!(!scene.is_alive(old));
+        assert!(scene.is_alive(new));
+        assert_eq!(names.get(old), None);
+        assert_eq!(names.get(new), Some(&"new"));
+        assert_eq!(names.remove(old), None);
+        assert_eq!(names.remove(new), Some("new"));
+    }
+}
@@ -1,15 +1,92 @@
 macro_rules! struct_events {
     (
-        keyboard: { $( $k_alias:ident : $k_sdl:ident ),* },
+        actions: { $( $a_alias:ident : $a_name:ident [ $( $a_key:ident ),* ] [ $( $a_pad:ident ( $a_pad_ctl:ident ) ),* ] ),* },
+        mouse: { $( $m_alias:ident : $m_sdl:ident ),* },
         else: { $( $e_alias:ident : $e_sdl:pat ),* }
     )
     => {
         use sdl2::EventPump;
+        use sdl2::GameControllerSubsystem;
+        use sdl2::controller::GameController;
+        use sdl2::keyboard::Keycode;
+        use std::collections::HashSet;
+        use self::bindings::{Bindings, PadControl};
+
+        /// How far an analog stick may be pushed, in `[0, 1]`, before it is
+        /// considered to move at all.
+        const STICK_DEADZONE: f64 = 0.25;
+
+        /// How far a trigger must be pulled, in `[0, 1]`, to count as held.
+        const TRIGGER_THRESHOLD: f64 = 0.5;
+
+
+        /// Something the player may do, whatever the keys which are bound to
+        /// it.
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+        pub enum Action {
+            $( $a_name ),*
+        }
+
+        impl Action {
+            /// Every action, in the order in which they were declared.
+            pub fn all() -> Vec<Action> {
+                vec![ $( Action::$a_name ),* ]
+            }
+
+            /// The name by which the action is known in configuration files.
+            pub fn name(self) -> &'static str {
+                match self {
+                    $( Action::$a_name => stringify!($a_name) ),*
+                }
+            }
+
+            pub fn from_name(name: &str) -> Option<Action> {
+                $(
+                    if name == stringify!($a_name) {
+                        return Some(Action::$a_name);
+                    }
+                )*
+
+                None
+            }
+
+            /// The keys bound to the action unless the player chose others.
+            pub fn default_keys(self) -> Vec<Keycode> {
+                match self {
+                    $( Action::$a_name => vec![ $( Keycode::$a_key ),* ] ),*
+                }
+            }
+
+            /// The controller buttons and triggers bound to the action.
+            pub fn default_pads(self) -> Vec<PadControl> {
+                #[allow(unused_imports)]
+                use sdl2::controller::Axis::*;
+                #[allow(unused_imports)]
+                use sdl2::controller::Button::*;
+
+                match self {
+                    $( Action::$a_name => vec![ $( PadControl::$a_pad($a_pad_ctl) ),* ] ),*
+                }
+            }
+        }
 
 
         pub struct ImmediateEvents {
             resize: Option<(u32, u32)>,
-            $( pub $k_alias : Option<bool> , )*
+
+            /// The key which was just pressed, whether or not it is bound to
+            /// an action. This is what lets the player pick new bindings.
+            pub key_pressed: Option<Keycode>,
+
+            /// Whether the mouse moved.
+            pub mouse_moved: bool,
+
+            /// How far the mouse wheel was scrolled, horizontally and
+            /// vertically.
+            pub wheel: (i32, i32),
+
+            $( pub $a_alias : Option<bool> , )*
+            $( pub $m_alias : Option<bool> , )*
             $( pub $e_alias : bool ),*
         }
 
@@ -17,7 +94,11 @@ macro_rules! struct_events {
             pub fn new() -> ImmediateEvents {
                 ImmediateEvents {
                     resize: None,
-                    $( $k_alias: None , )*
+                    key_pressed: None,
+                    mouse_moved: false,
+                    wheel: (0, 0),
+                    $( $a_alias: None , )*
+                    $( $m_alias: None , )*
                     $( $e_alias: false ),*
                 }
             }
@@ -28,25 +109,133 @@ macro_rules! struct_events {
             pump: EventPump,
             pub now: ImmediateEvents,
 
-            $( pub $k_alias: bool ),*
+            /// Which keys trigger which actions.
+            pub bindings: Bindings,
+
+            /// The keys which are currently held down.
+            held_keys: HashSet<Keycode>,
+
+            /// The game controllers which are plugged in, by instance id.
+            controllers: HashMap<i32, GameController>,
+            controller_subsystem: GameControllerSubsystem,
+
+            /// The controller buttons and triggers which are currently held
+            /// down, along with the instance id of their controller.
+            held_pads: HashSet<(i32, PadControl)>,
+
+            /// The position of the left stick of every controller which moved
+            /// it, by instance id, before the deadzone is applied.
+            raw_sticks: HashMap<i32, (f64, f64)>,
+
+            /// The direction in which the player pushes an analog stick, with
+            /// both coordinates in `[-1, 1]` and a length of at most 1. It is
+            /// `(0, 0)` when no stick is pushed past the deadzone.
+            pub stick: (f64, f64),
+
+            /// The position of the mouse in the window, in pixels.
+            pub mouse: (f64, f64),
+
+            $( pub $m_alias: bool, )*
+            $( pub $a_alias: bool ),*
         }
 
         impl Events {
-            pub fn new(pump: EventPump) -> Events {
+            pub fn new(pump: EventPump, bindings: Bindings, controllers: GameControllerSubsystem) -> Events {
                 Events {
                     pump: pump,
                     now: ImmediateEvents::new(),
+                    bindings: bindings,
+                    held_keys: HashSet::new(),
+                    controllers: HashMap::new(),
+                    controller_subsystem: controllers,
+                    held_pads: HashSet::new(),
+                    raw_sticks: HashMap::new(),
+                    stick: (0.0, 0.0),
+                    mouse: (0.0, 0.0),
+
+                    $( $m_alias: false, )*
+
+                    $( $a_alias: false ),*
+                }
+            }
+
+            /// Update whether `action` is held, and remember whether it was
+            /// just pressed or released.
+            fn set_held(&mut self, action: Action, held: bool) {
+                match action {
+                    $(
+                        Action::$a_name => {
+                            // Prevent multiple presses when keeping a key down
+                            if self.$a_alias != held {
+                                self.now.$a_alias = Some(held);
+                            }
+
+                            self.$a_alias = held;
+                        }
+                    ),*
+                }
+            }
+
+            /// Recompute the state of `actions`. An action stays held for as
+            /// long as any of its keys or controller buttons is.
+            fn refresh(&mut self, actions: Vec<Action>) {
+                for action in actions {
+                    let held =
+                        self.bindings.keys(action).iter()
+                            .any(|k| self.held_keys.contains(k)) ||
+                        self.bindings.pads(action).iter()
+                            .any(|&pad| self.held_pads.iter().any(|&(_, p)| p == pad));
+
+                    self.set_held(action, held);
+                }
+            }
+
+            /// Press or release a button or trigger of the controller `which`.
+            fn set_pad(&mut self, which: i32, pad: PadControl, held: bool) {
+                if held {
+                    self.held_pads.insert((which, pad));
+                } else {
+                    self.held_pads.remove(&(which, pad));
+                }
+
+                let actions = self.bindings.actions_for_pad(pad);
+                self.refresh(actions);
+            }
 
-                    $( $k_alias: false ),*
+            /// Applies the deadzone to the raw position of the sticks. Past
+            /// the deadzone, the length grows from 0 to 1 again, so that the
+            /// ship can move slowly.
+            //? With several controllers, the stick which is pushed the
+            //? furthest wins, so that one which rests does not cancel the
+            //? others. Ties go to the controller which was plugged in first.
+            fn update_stick(&mut self) {
+                let mut ids: Vec<_> = self.raw_sticks.keys().cloned().collect();
+                ids.sort();
+
+                self.stick = (0.0, 0.0);
+                let mut longest = 0.0;
+
+                for id in ids {
+                    let (x, y) = self.raw_sticks[&id];
+                    let length = (x * x + y * y).sqrt();
+
+                    if length > STICK_DEADZONE && length > longest {
+                        let scale = ((length - STICK_DEADZONE) / (1.0 - STICK_DEADZONE)).min(1.0) / length;
+                        self.stick = (x * scale, y * scale);
+                        longest = length;
+                    }
                 }
             }
 
             pub fn pump(&mut self, renderer: &mut ::sdl2::render::Renderer) {
                 self.now = ImmediateEvents::new();
 
-                for event in self.pump.poll_iter() {
+                //? Gather the events first, as handling them needs to borrow
+                //? the rest of `self`.
+                let events: Vec<_> = self.pump.poll_iter().collect();
+
+                for event in events {
                     use sdl2::event::Event::*;
-                    use sdl2::keyboard::Keycode::*;
                     use sdl2::event::WindowEventId::Resized;
 
                     match event {
@@ -54,32 +243,121 @@ macro_rules! struct_events {
                             self.now.resize = Some(renderer.output_size().unwrap());
                         },
 
-                        KeyDown { keycode, .. } => match keycode {
-                            $(
-                                Some($k_sdl) => {
+                        KeyDown { keycode: Some(keycode), .. } => {
+                            // Was previously not pressed?
+                            if self.held_keys.insert(keycode) {
+                                // Key pressed
+                                self.now.key_pressed = Some(keycode);
+                            }
+
+                            let actions = self.bindings.actions_for(keycode);
+                            self.refresh(actions);
+                        },
+
+                        KeyUp { keycode: Some(keycode), .. } => {
+                            // Key released
+                            self.held_keys.remove(&keycode);
+
+                            let actions = self.bindings.actions_for(keycode);
+                            self.refresh(actions);
+                        },
+
+                        MouseMotion { x, y, .. } => {
+                            self.mouse = (x as f64, y as f64);
+                            self.now.mouse_moved = true;
+                        },
+
+                        MouseButtonDown { mouse_btn, x, y, .. } => {
+                            self.mouse = (x as f64, y as f64);
 
-                                    // Prevent multiple presses when keeping a key down
-                                    // Was previously not pressed?
-                                    if !self.$k_alias {
-                                        // Key pressed
-                                        self.now.$k_alias = Some(true);
+                            match mouse_btn {
+                                $(
+                                    ::sdl2::mouse::MouseButton::$m_sdl => {
+                                        self.now.$m_alias = Some(true);
+                                        self.$m_alias = true;
                                     }
+                                ),*
+                                _ => {}
+                            }
+                        },
+
+                        MouseButtonUp { mouse_btn, x, y, .. } => {
+                            self.mouse = (x as f64, y as f64);
 
-                                    self.$k_alias = true;
-                                }
-                            ),*
-                            _ => {}
+                            match mouse_btn {
+                                $(
+                                    ::sdl2::mouse::MouseButton::$m_sdl => {
+                                        self.now.$m_alias = Some(false);
+                                        self.$m_alias = false;
+                                    }
+                                ),*
+                                _ => {}
+                            }
                         },
 
-                        KeyUp { keycode, .. } => match keycode {
-                            $(
-                                Some($k_sdl) => {
-                                    // Key released
-                                    self.now.$k_alias = Some(false);
-                                    self.$k_alias = false;
-                                }
-                            ),*
-                            _ => {}
+                        MouseWheel { x, y, .. } => {
+                            self.now.wheel.0 += x;
+                            self.now.wheel.1 += y;
+                        },
+
+                        ControllerDeviceAdded { which, .. } => {
+                            match self.controller_subsystem.open(which as u32) {
+                                Ok(controller) => {
+                                    println!("Controller connected: {}", controller.name());
+                                    self.controllers.insert(controller.instance_id(), controller);
+                                },
+                                Err(e) => println!("Could not open controller {}: {:?}", which, e),
+                            }
+                        },
+
+                        ControllerDeviceRemoved { which, .. } => {
+                            if self.controllers.remove(&which).is_some() {
+                                println!("Controller disconnected");
+                            }
+
+                            // Release whatever the controller was holding.
+                            let released: Vec<_> = self.held_pads.iter()
+                                .filter(|&&(id, _)| id == which)
+                                .cloned()
+                                .collect();
+
+                            for (id, pad) in released {
+                                self.set_pad(id, pad, false);
+                            }
+
+                            self.raw_sticks.remove(&which);
+                            self.update_stick();
+                        },
+
+                        ControllerButtonDown { which, button, .. } =>
+                            self.set_pad(which, PadControl::Button(button), true),
+
+                        ControllerButtonUp { which, button, .. } =>
+                            self.set_pad(which, PadControl::Button(button), false),
+
+                        ControllerAxisMotion { which, axis, value, .. } => {
+                            use sdl2::controller::Axis;
+                            let value = (value as f64 / 32767.0).max(-1.0);
+
+                            match axis {
+                                Axis::LeftX => {
+                                    self.raw_sticks.entry(which).or_insert((0.0, 0.0)).0 = value;
+                                    self.update_stick();
+                                },
+                                Axis::LeftY => {
+                                    self.raw_sticks.entry(which).or_insert((0.0, 0.0)).1 = value;
+                                    self.update_stick();
+                                },
+                                Axis::TriggerLeft | Axis::TriggerRight => {
+                                    let pad = PadControl::Trigger(axis);
+                                    let held = value > TRIGGER_THRESHOLD;
+
+                                    if held != self.held_pads.contains(&(which, pad)) {
+                                        self.set_pad(which, pad, held);
+                                    }
+                                },
+                                _ => {}
+                            }
                         },
 
                         $(
@@ -92,6 +370,190 @@ macro_rules! struct_events {
                     }
                 }
             }
+
+            /// Discards the events waiting in SDL's queue, so that the window
+            /// stays responsive while the input comes from somewhere else.
+            /// Returns whether the window was asked to close.
+            pub fn discard_pending(&mut self) -> bool {
+                let mut closed = false;
+
+                for event in self.pump.poll_iter() {
+                    if let ::sdl2::event::Event::Quit { .. } = event {
+                        closed = true;
+                    }
+                }
+
+                closed
+            }
+
+            /// Describes the current state of the input as a list of tokens
+            /// without whitespace: `action` for an action which is held down,
+            /// `+action`/`-action` for an action which was just
+            /// pressed/released, `!event` for any other event which just
+            /// happened, `key=<code>` for the key which was just pressed,
+            /// `stick=<x>,<y>` for the direction of the analog stick, and
+            /// `mouse=<x>,<y>`/`wheel=<x>,<y>` for the position of the mouse
+            /// and the motion of its wheel. Mouse buttons work like actions.
+            pub fn to_tokens(&self) -> Vec<String> {
+                let mut tokens = vec![];
+
+                if let Some((w, h)) = self.now.resize {
+                    tokens.push(format!("resize={}x{}", w, h));
+                }
+
+                if let Some(key) = self.now.key_pressed {
+                    tokens.push(format!("key={}", key as i32));
+                }
+
+                if self.stick != (0.0, 0.0) {
+                    tokens.push(format!("stick={},{}", self.stick.0, self.stick.1));
+                }
+
+                tokens.push(format!("mouse={},{}", self.mouse.0, self.mouse.1));
+
+                if self.now.mouse_moved {
+                    tokens.push("!mouse_moved".to_string());
+                }
+
+                if self.now.wheel != (0, 0) {
+                    tokens.push(format!("wheel={},{}", self.now.wheel.0, self.now.wheel.1));
+                }
+
+                $(
+                    if self.$m_alias {
+                        tokens.push(stringify!($m_alias).to_string());
+                    }
+
+                    match self.now.$m_alias {
+                        Some(true) => tokens.push(concat!("+", stringify!($m_alias)).to_string()),
+                        Some(false) => tokens.push(concat!("-", stringify!($m_alias)).to_string()),
+                        None => {}
+                    }
+                )*
+
+                $(
+                    if self.$a_alias {
+                        tokens.push(stringify!($a_alias).to_string());
+                    }
+
+                    match self.now.$a_alias {
+                        Some(true) => tokens.push(concat!("+", stringify!($a_alias)).to_string()),
+                        Some(false) => tokens.push(concat!("-", stringify!($a_alias)).to_string()),
+                        None => {}
+                    }
+                )*
+
+                $(
+                    if self.now.$e_alias {
+                        tokens.push(concat!("!", stringify!($e_alias)).to_string());
+                    }
+                )*
+
+                tokens
+            }
+
+            /// Replaces the state of the input by the one described by
+            /// `tokens`, as produced by `to_tokens`.
+            pub fn apply_tokens(&mut self, tokens: &[String]) -> Result<(), String> {
+                self.now = ImmediateEvents::new();
+                self.stick = (0.0, 0.0);
+                $( self.$m_alias = false; )*
+                $( self.$a_alias = false; )*
+
+                for token in tokens {
+                    let token = token.as_str();
+
+                    if token.starts_with("resize=") {
+                        let size: Vec<_> = token["resize=".len()..].split('x')
+                            .filter_map(|n| n.parse().ok())
+                            .collect();
+
+                        if size.len() != 2 {
+                            return Err(format!("invalid size in `{}`", token));
+                        }
+
+                        self.now.resize = Some((size[0], size[1]));
+                    }
+                    else if token.starts_with("key=") {
+                        let key = token["key=".len()..].parse().ok()
+                            .and_then(Keycode::from_i32);
+
+                        if key.is_none() {
+                            return Err(format!("invalid key in `{}`", token));
+                        }
+
+                        self.now.key_pressed = key;
+                    }
+                    else if token.starts_with("stick=") {
+                        let stick: Vec<f64> = token["stick=".len()..].split(',')
+                            .filter_map(|n| n.parse().ok())
+                            .collect();
+
+                        if stick.len() != 2 {
+                            return Err(format!("invalid stick position in `{}`", token));
+                        }
+
+                        self.stick = (stick[0], stick[1]);
+                    }
+                    else if token.starts_with("mouse=") {
+                        let pos: Vec<f64> = token["mouse=".len()..].split(',')
+                            .filter_map(|n| n.parse().ok())
+                            .collect();
+
+                        if pos.len() != 2 {
+                            return Err(format!("invalid mouse position in `{}`", token));
+                        }
+
+                        self.mouse = (pos[0], pos[1]);
+                    }
+                    else if token.starts_with("wheel=") {
+                        let wheel: Vec<i32> = token["wheel=".len()..].split(',')
+                            .filter_map(|n| n.parse().ok())
+                            .collect();
+
+                        if wheel.len() != 2 {
+                            return Err(format!("invalid wheel motion in `{}`", token));
+                        }
+
+                        self.now.wheel = (wheel[0], wheel[1]);
+                    }
+                    else if token == "!mouse_moved" {
+                        self.now.mouse_moved = true;
+                    }
+                    $(
+                        else if token == stringify!($m_alias) {
+                            self.$m_alias = true;
+                        }
+                        else if token == concat!("+", stringify!($m_alias)) {
+                            self.now.$m_alias = Some(true);
+                        }
+                        else if token == concat!("-", stringify!($m_alias)) {
+                            self.now.$m_alias = Some(false);
+                        }
+                    )*
+                    $(
+                        else if token == stringify!($a_alias) {
+                            self.$a_alias = true;
+                        }
+                        else if token == concat!("+", stringify!($a_alias)) {
+                            self.now.$a_alias = Some(true);
+                        }
+                        else if token == concat!("-", stringify!($a_alias)) {
+                            self.now.$a_alias = Some(false);
+                        }
+                    )*
+                    $(
+                        else if token == concat!("!", stringify!($e_alias)) {
+                            self.now.$e_alias = true;
+                        }
+                    )*
+                    else {
+                        return Err(format!("unknown input `{}`", token));
+                    }
+                }
+
+                Ok(())
+            }
         }
     }
-}
\ No newline at end of file
+}
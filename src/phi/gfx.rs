@@ -1,8 +1,11 @@
 use phi::Phi;
+use phi::assets::AssetError;
 use phi::data::Rectangle;
 use std::cell::RefCell;
 use std::path::Path;
 use std::rc::Rc;
+use sdl2::pixels::Color;
+use sdl2::rect::Point;
 use sdl2::render::{Renderer, Texture};
 use sdl2_image::LoadTexture;
 
@@ -10,6 +13,74 @@ use sdl2_image::LoadTexture;
 /// of the window.
 pub trait Renderable {
     fn render(&self, renderer: &mut Renderer, dest: Rectangle);
+
+    /// Renders the component to `dest`, transformed as told by `transform`.
+    fn render_ex(&self, renderer: &mut Renderer, dest: Rectangle, transform: Transform);
+}
+
+/// How a sprite should be drawn, besides where.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform {
+    /// The clockwise rotation of the sprite, in degrees.
+    pub angle: f64,
+
+    /// The point around which the sprite is rotated, as a fraction of the
+    /// size of where it is drawn from its top-left corner, or `None` for its
+    /// center. `(0.0, 1.0)` is the bottom-left corner, whatever the size.
+    pub pivot: Option<(f64, f64)>,
+
+    pub flip_h: bool,
+    pub flip_v: bool,
+
+    /// How opaque the sprite is, from 0 (invisible) to 255.
+    pub alpha: u8,
+
+    /// The color by which the sprite's colors are multiplied. White leaves
+    /// them unchanged.
+    pub tint: Color,
+}
+
+impl Default for Transform {
+    fn default() -> Transform {
+        Transform {
+            angle: 0.0,
+            pivot: None,
+            flip_h: false,
+            flip_v: false,
+            alpha: 255,
+            tint: Color::RGB(255, 255, 255),
+        }
+    }
+}
+
+//? These let us write, e.g., `Transform::default().rotated(90.0).faded(128)`.
+impl Transform {
+    pub fn rotated(self, angle: f64) -> Transform {
+        Transform { angle: angle, ..self }
+    }
+
+    pub fn rotated_about(self, angle: f64, pivot: (f64, f64)) -> Transform {
+        Transform { angle: angle, pivot: Some(pivot), ..self }
+    }
+
+    pub fn flipped(self, flip_h: bool, flip_v: bool) -> Transform {
+        Transform { flip_h: flip_h, flip_v: flip_v, ..self }
+    }
+
+    pub fn faded(self, alpha: u8) -> Transform {
+        Transform { alpha: alpha, ..self }
+    }
+
+    pub fn tinted(self, tint: Color) -> Transform {
+        Transform { tint: tint, ..self }
+    }
+
+    /// The point around which a sprite drawn to `dest` is rotated, in pixels
+    /// from the top-left corner of `dest`.
+    pub fn pivot_in(&self, dest: Rectangle) -> (f64, f64) {
+        let (x, y) = self.pivot.unwrap_or((0.5, 0.5));
+        (dest.w * x, dest.h * y)
+    }
 }
 
 #[derive(Clone)]
@@ -35,9 +106,13 @@ impl Sprite {
     }
 
     /// Creates a new sprite from an image file located at the given path.
-    /// Returns `Some` if the file could be read, and `None` otherwise.
-    pub fn load(renderer: &Renderer, path: &str) -> Option<Sprite> {
-        renderer.load_texture(Path::new(path)).ok().map(Sprite::new)
+    /// Returns an error naming the file if it could not be read.
+    ///
+    /// This loads the file every time; `Assets::sprite` only does so once.
+    pub fn load(renderer: &Renderer, path: &Path) -> Result<Sprite, AssetError> {
+        renderer.load_texture(path)
+            .map(Sprite::new)
+            .map_err(|e| AssetError::at(path, e))
     }
 
     // Returns the dimensions of the region.
@@ -72,138 +147,258 @@ impl Renderable for Sprite {
     fn render(&self, renderer: &mut Renderer, dest: Rectangle) {
         renderer.copy(&mut self.tex.borrow_mut(), self.src.to_sdl(), dest.to_sdl()).unwrap()
     }
+
+    fn render_ex(&self, renderer: &mut Renderer, dest: Rectangle, transform: Transform) {
+        let mut tex = self.tex.borrow_mut();
+        let (r, g, b) = match transform.tint {
+            Color::RGB(r, g, b) | Color::RGBA(r, g, b, _) => (r, g, b),
+        };
+
+        //? The modulation applies to the whole texture, which other sprites
+        //? may share, so it is undone right after the copy.
+        tex.set_color_mod(r, g, b);
+        tex.set_alpha_mod(transform.alpha);
+
+        //? SDL finds the center of `dest` by itself, so we only tell it where
+        //? the pivot is if it was moved.
+        let pivot = transform.pivot.map(|_| {
+            let (x, y) = transform.pivot_in(dest);
+            Point::new(x.round() as i32, y.round() as i32)
+        });
+
+        renderer.copy_ex(&tex, self.src.to_sdl(), dest.to_sdl(), transform.angle,
+                         pivot, transform.flip_h, transform.flip_v).unwrap();
+
+        tex.set_color_mod(255, 255, 255);
+        tex.set_alpha_mod(255);
+    }
 }
 
-#[derive(Clone)]
-pub struct AnimatedSprite {
-    /// The frames that will be rendered, in order.
-    sprites: Vec<Sprite>,
+/// How an animation goes on once it has shown its last frame.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Playback {
+    /// Start over from the first frame.
+    Loop,
 
-    /// The time it takes to get from one frame to the next, in seconds.
-    frame_delay: f64,
+    /// Stay on the last frame, and count as finished.
+    Once,
 
-    /// The total time that the sprite has been alive, from which the current
-    /// frame is derived.
-    current_time: f64,
+    /// Go back and forth between the first and the last frames.
+    PingPong,
 }
 
-impl AnimatedSprite {
-    /// Creates a new animated sprite initialized at time 0.
-    pub fn new(sprites: Vec<Sprite>, frame_delay: f64) -> AnimatedSprite {
-        AnimatedSprite {
-            sprites: sprites,
-            frame_delay: frame_delay,
-            current_time: 0.0,
-        }
-    }
+/// The playback state of an animation, kept apart from the frames it selects
+/// so that it can be advanced without a renderer.
+#[derive(Clone, Copy, Debug)]
+pub struct Animation {
+    /// The number of frames composing the animation.
+    frames: usize,
 
-    /// Creates a new animated sprite which goes to the next frame `fps` times
-    /// every second.
-    pub fn with_fps(sprites: Vec<Sprite>, fps: f64) -> AnimatedSprite {
-        //? Logically, a value of 0FPS might mean "stop changing frames".
-        //? However, there's not really a need for this functionality in this
-        //? game.
-        //?
-        //? If you would like to implement this functionality yourself, I would
-        //? suggest that you add a `current_frame` attribute to `AnimatedSprite`
-        //? which is used whenever `frame_delay`, in this scenario of type
-        //? `Option<f64>`, is `None` (where `None` == infinity).
-        //?
-        //? Then, when `with_fps` or `set_fps` gets a value of 0, you compute
-        //? the current frame and assign it to `current_frame`, then set
-        //? `frame_delay` to `None`. The rest is yours to solve.
-        if fps == 0.0 {
-            panic!("Passed 0 to AnimatedSprite::with_fps");
-        }
+    /// The time it takes to get from one frame to the next, in seconds, or
+    /// `None` if the animation is frozen on its current frame.
+    //? `None` stands for an infinite delay, which is what a value of 0FPS
+    //? means.
+    frame_delay: Option<f64>,
 
-        AnimatedSprite::new(sprites, 1.0 / fps)
+    /// How far the animation went, in frames. This goes from 0 to `frames`
+    /// for `Once` and `Loop`, and twice as far for `PingPong`, which then
+    /// goes back.
+    position: f64,
+
+    playback: Playback,
+
+    /// Whether the time added to the animation is ignored.
+    paused: bool,
+
+    /// Whether an animation played `Once` got to its end.
+    finished: bool,
+}
+
+impl Animation {
+    /// Creates a new looping animation initialized at time 0.
+    pub fn new(frames: usize, frame_delay: f64) -> Animation {
+        Animation {
+            frames: frames,
+            frame_delay: if frame_delay == 0.0 { None } else { Some(frame_delay) },
+            position: 0.0,
+            playback: Playback::Loop,
+            paused: false,
+            finished: false,
+        }
     }
 
+    /// Creates a new animation which goes to the next frame `fps` times every
+    /// second. A value of 0 freezes it on its first frame.
+    pub fn with_fps(frames: usize, fps: f64) -> Animation {
+        let mut anim = Animation::new(frames, 0.0);
+        anim.set_fps(fps);
+        anim
+    }
 
     // The number of frames composing the animation.
     pub fn frames(&self) -> usize {
-        self.sprites.len()
+        self.frames
+    }
+
+    /// The number of frames the animation goes through every second, which
+    /// is 0 if it is frozen.
+    pub fn fps(&self) -> f64 {
+        self.frame_delay.map(|delay| 1.0 / delay).unwrap_or(0.0)
     }
 
     /// Set the time it takes to get from one frame to the next, in seconds.
-    /// If the value is negative, then we "rewind" the animation.
+    /// If the value is negative, then we "rewind" the animation. If it is 0,
+    /// then the animation stays on its current frame, as with `set_fps(0.0)`.
     pub fn set_frame_delay(&mut self, frame_delay: f64) {
-        self.frame_delay = frame_delay;
+        self.frame_delay = if frame_delay == 0.0 { None } else { Some(frame_delay) };
     }
 
     /// Set the number of frames the animation goes through every second.
-    /// If the value is negative, then we "rewind" the animation.
+    /// If the value is negative, then we "rewind" the animation. If it is 0,
+    /// then the animation stays on its current frame.
     pub fn set_fps(&mut self, fps: f64) {
-        if fps == 0.0 {
-            panic!("Passed 0 to AnimatedSprite::set_fps");
-        }
+        self.frame_delay = if fps == 0.0 { None } else { Some(1.0 / fps) };
+    }
 
-        self.set_frame_delay(1.0 / fps);
+    pub fn playback(&self) -> Playback {
+        self.playback
     }
 
-    /// Adds a certain amount of time, in seconds, to the `current_time` of the
-    /// animated sprite, so that it knows when it must go to the next frame.
-    pub fn add_time(&mut self, dt: f64) {
-        self.current_time += dt;
+    /// Changes how the animation goes on once it gets to its last frame,
+    /// keeping the frame which is currently shown.
+    pub fn set_playback(&mut self, playback: Playback) {
+        let frame = self.current_frame();
+        self.playback = playback;
+        self.set_current_frame(frame);
+    }
 
-        // If we decide to go "back in time", this allows us to select the
-        // last frame whenever we reach a negative one.
-        if self.current_time < 0.0 {
-            self.current_time = (self.frames() - 1) as f64 * self.frame_delay;
-        }
+    /// Stops the animation on its current frame, until it is resumed.
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Whether an animation played `Once` got to its end. Animations which
+    /// loop never finish.
+    pub fn is_finished(&self) -> bool {
+        self.finished
     }
 
-    pub fn load_frames(phi: &mut Phi, descr: AnimatedSpriteDescr) -> Vec<Sprite> {
-        // Read the asteroid's image from the filesystem and construct an
-        // animated sprite out of it.
+    /// Adds a certain amount of time, in seconds, to the animation, so that it
+    /// knows when it must go to the next frame.
+    pub fn add_time(&mut self, dt: f64) {
+        let delay = match self.frame_delay {
+            Some(delay) if !self.paused && !self.finished && self.frames > 0 => delay,
+            _ => return,
+        };
+
+        self.position += dt / delay;
 
-        let spritesheet = Sprite::load(&mut phi.renderer, descr.image_path).unwrap();
-        let mut frames = Vec::with_capacity(descr.total_frames);
+        let frames = self.frames as f64;
 
-        for yth in 0..descr.frames_high {
-            for xth in 0..descr.frames_wide {
-                if descr.frames_wide * yth + xth >= descr.total_frames {
-                    break;
+        match self.playback {
+            Playback::Once => {
+                // Whichever way the animation is played, it stops at the
+                // frame it was heading for.
+                if self.position >= frames {
+                    self.position = frames - 1.0;
+                    self.finished = true;
+                } else if self.position < 0.0 {
+                    self.position = 0.0;
+                    self.finished = true;
                 }
+            },
+
+            Playback::Loop => {
+                //? If we decide to go "back in time", this wraps negative
+                //? positions around to the last frame.
+                self.position = wrap(self.position, frames);
+            },
+
+            Playback::PingPong => {
+                //? Going there and back, the first and last frames are only
+                //? shown once per trip.
+                let trip = 2.0 * (frames - 1.0);
+                self.position = if trip > 0.0 { wrap(self.position, trip) } else { 0.0 };
+            },
+        }
+    }
 
-                frames.push(
-                    spritesheet.region(Rectangle {
-                        w: descr.frame_w,
-                        h: descr.frame_h,
-                        x: descr.frame_w * xth as f64,
-                        y: descr.frame_h * yth as f64,
-                    }).unwrap());
-            }
+    /// The index of the frame which should currently be shown.
+    pub fn current_frame(&self) -> usize {
+        let frame = self.position as usize;
+
+        if self.frames <= 1 {
+            0
+        } else if self.playback == Playback::PingPong && frame >= self.frames {
+            // On the way back
+            2 * (self.frames - 1) - frame
+        } else {
+            frame.min(self.frames - 1)
         }
+    }
 
-        frames
+    /// Shows the given frame, from which the animation then goes on.
+    pub fn set_current_frame(&mut self, frame: usize) {
+        self.position = frame.min(self.frames.saturating_sub(1)) as f64;
+        self.finished = false;
     }
 }
 
-impl Renderable for AnimatedSprite {
-    /// Renders the current frame of the sprite.
-    fn render(&self, renderer: &mut Renderer, dest: Rectangle) {
-        let current_frame =
-        (self.current_time / self.frame_delay) as usize % self.frames();
+/// The remainder of `x` divided by `n`, which is positive even if `x` is not.
+fn wrap(x: f64, n: f64) -> f64 {
+    let rem = x % n;
+    if rem < 0.0 { rem + n } else { rem }
+}
+
+/// Cuts the frames described by `descr` out of their image, in order.
+pub fn load_frames(phi: &mut Phi, descr: AnimatedSpriteDescr) -> Result<Vec<Sprite>, AssetError> {
+    // Read the image from the filesystem and split it into frames.
+
+    let spritesheet = phi.assets.sprite(&phi.renderer, descr.image_path)?;
+    descr.check_fits(spritesheet.size())
+        .map_err(|e| AssetError::Corrupt(phi.assets.path(descr.image_path), e))?;
+
+    let mut frames = Vec::with_capacity(descr.total_frames);
 
-        let sprite = &self.sprites[current_frame];
-        sprite.render(renderer, dest);
+    for yth in 0..descr.frames_high {
+        for xth in 0..descr.frames_wide {
+            if descr.frames_wide * yth + xth >= descr.total_frames {
+                break;
+            }
+
+            frames.push(
+                spritesheet.region(Rectangle {
+                    w: descr.frame_w,
+                    h: descr.frame_h,
+                    x: descr.frame_w * xth as f64,
+                    y: descr.frame_h * yth as f64,
+                }).unwrap());
+        }
     }
+
+    Ok(frames)
 }
 
 pub trait CopySprite<T> {
     fn copy_sprite(&mut self, sprite: &T, dest: Rectangle);
+    fn copy_sprite_ex(&mut self, sprite: &T, dest: Rectangle, transform: Transform);
 }
 
 impl<'window> CopySprite<Sprite> for Renderer<'window> {
     fn copy_sprite(&mut self, renderable: &Sprite, dest: Rectangle) {
         renderable.render(self, dest);
     }
-}
 
-impl<'window> CopySprite<AnimatedSprite> for Renderer<'window> {
-    fn copy_sprite(&mut self, renderable: &AnimatedSprite, dest: Rectangle) {
-        renderable.render(self, dest);
+    fn copy_sprite_ex(&mut self, renderable: &Sprite, dest: Rectangle, transform: Transform) {
+        renderable.render_ex(self, dest, transform);
     }
 }
 
@@ -222,3 +417,59 @@ pub struct AnimatedSpriteDescr<'a> {
     pub frame_h: f64,
 }
 
+impl<'a> AnimatedSpriteDescr<'a> {
+    /// Checks that every frame lies inside of an image of the given size,
+    /// and explains what is wrong otherwise.
+    pub fn check_fits(&self, image_size: (f64, f64)) -> Result<(), String> {
+        if self.total_frames > self.frames_wide * self.frames_high {
+            return Err(format!("{} frames do not fit in a grid of {}x{}",
+                               self.total_frames, self.frames_wide, self.frames_high));
+        }
+
+        //? The last row may be incomplete, so only the rows which hold a
+        //? frame count.
+        let rows =
+            if self.frames_wide == 0 { 0 }
+            else { (self.total_frames + self.frames_wide - 1) / self.frames_wide };
+        let grid_w = self.frame_w * self.frames_wide as f64;
+        let grid_h = self.frame_h * rows as f64;
+
+        if grid_w > image_size.0 || grid_h > image_size.1 {
+            return Err(format!("a grid of {}x{} frames of {}x{} pixels does not fit in the {}x{} image",
+                               self.frames_wide, rows, self.frame_w, self.frame_h,
+                               image_size.0, image_size.1));
+        }
+
+        Ok(())
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use phi::data::Rectangle;
+    use super::{Animation, Transform};
+
+    #[test]
+    fn a_frame_delay_of_zero_freezes_the_animation() {
+        let mut anim = Animation::new(4, 0.1);
+        anim.add_time(0.15);
+        anim.set_frame_delay(0.0);
+        anim.add_time(1.0);
+
+        assert_eq!(anim.current_frame(), 1);
+        assert_eq!(anim.fps(), 0.0);
+    }
+
+    #[test]
+    fn pivots_scale_with_where_the_sprite_is_drawn() {
+        let dest = Rectangle { x: 100.0, y: 50.0, w: 40.0, h: 20.0 };
+
+        assert_eq!(Transform::default().rotated(90.0).pivot_in(dest), (20.0, 10.0));
+        assert_eq!(Transform::default().rotated_about(90.0, (0.0, 1.0)).pivot_in(dest), (0.0, 20.0));
+
+        let twice = Rectangle { w: 80.0, h: 40.0, ..dest };
+        assert_eq!(Transform::default().rotated_about(90.0, (0.25, 0.5)).pivot_in(twice), (20.0, 20.0));
+    }
+}
@@ -2,21 +2,42 @@
 mod events;
 pub mod gfx;
 pub mod data;
+pub mod replay;
+pub mod bindings;
+pub mod paths;
+pub mod assets;
+pub mod sheet;
+pub mod shapes;
+pub mod broadphase;
+pub mod ecs;
 
-use self::gfx::Sprite;
+use self::assets::{AssetError, Assets};
+use self::data::seeded_rng;
+use self::data::Rectangle;
+use self::gfx::{CopySprite, Sprite};
+use self::replay::{Recorder, Replay};
+use rand::{Rng, XorShiftRng};
 use sdl2::render::Renderer;
 use sdl2::pixels::Color;
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::PathBuf;
 
 struct_events! {
-    keyboard: {
-        key_escape: Escape,
-        key_up: Up,
-        key_down: Down,
-        key_left: Left,
-        key_right: Right,
-        key_space: Space
+    actions: {
+        move_up: MoveUp [Up, W] [Button(DPadUp)],
+        move_down: MoveDown [Down, S] [Button(DPadDown)],
+        move_left: MoveLeft [Left, A] [Button(DPadLeft)],
+        move_right: MoveRight [Right, D] [Button(DPadRight)],
+        fire: Fire [Space] [Button(A), Trigger(TriggerRight)],
+        switch_weapon: SwitchWeapon [Q] [Button(Y)],
+        pause: Pause [Escape, P] [Button(Start)],
+        confirm: Confirm [Return, Space] [Button(A), Button(Start)],
+        back: Back [Escape] [Button(B), Button(Back)]
+    },
+    mouse: {
+        mouse_left: Left,
+        mouse_middle: Middle,
+        mouse_right: Right
     },
     else: {
         quit: Quit { .. }
@@ -29,41 +50,66 @@ struct_events! {
 pub struct Phi<'window> {
     pub events: Events,
     pub renderer: Renderer<'window>,
-    cached_fonts: HashMap<(&'static str, i32), ::sdl2_ttf::Font>,
-    ttf_context: ::sdl2_ttf::Sdl2TtfContext,
+    pub assets: Assets,
     allocated_channels: isize,
+    seeds: XorShiftRng,
+
+    /// The number of ships the player starts every game with.
+    pub lives: u32,
+
+    /// Whether the input comes from a replay rather than from the player.
+    pub replaying: bool,
+
+    /// The size of the screen on which the views draw, in logical pixels.
+    resolution: (u32, u32),
 }
 
 impl<'window> Phi<'window> {
-    fn new(events: Events, renderer: Renderer<'window>, ttf_context: ::sdl2_ttf::Sdl2TtfContext) -> Phi<'window> {
+    fn new(events: Events, mut renderer: Renderer<'window>, assets: Assets, seed: u64, lives: u32, replaying: bool, resolution: (u32, u32)) -> Phi<'window> {
+        //? SDL scales whatever is drawn to fit in the window, keeping the
+        //? aspect ratio by adding black bars on the sides. It also maps the
+        //? position of the mouse back to logical pixels.
+        renderer.set_logical_size(resolution.0, resolution.1).unwrap();
+
         // We start with 32 mixer channels, which we may grow if necessary.
         let allocated_channels = 32;
         ::sdl2_mixer::allocate_channels(allocated_channels);
         Phi {
             events: events,
             renderer: renderer,
-            cached_fonts: HashMap::new(),
-            ttf_context: ttf_context,
+            assets: assets,
             allocated_channels: allocated_channels,
+            seeds: seeded_rng(seed),
+            lives: lives,
+            replaying: replaying,
+            resolution: resolution,
         }
     }
 
+    /// The size of the screen on which the views draw. This is the logical
+    /// resolution of the game, whatever the size of the window.
     pub fn output_size(&self) -> (f64, f64) {
-        let (w, h) = self.renderer.output_size().unwrap();
-        (w as f64, h as f64)
+        (self.resolution.0 as f64, self.resolution.1 as f64)
     }
 
-    pub fn ttf_str_sprite(&mut self, text: &str, font_path: &'static str, size: i32, color: Color) -> Option<Sprite> {
-        if let Some(font) = self.cached_fonts.get(&(font_path, size)){
-            return font.render(text).blended(color).ok()
-            .and_then(|surface| self.renderer.create_texture_from_surface(&surface).ok())
-            .map(Sprite::new);
-        }
-        self.ttf_context.load_font(Path::new(&font_path), size as u16).ok()
-        .and_then(|font|{
-            self.cached_fonts.insert((font_path, size), font);
-            self.ttf_str_sprite(text, font_path, size, color)
-        })
+    pub fn ttf_str_sprite(&mut self, text: &str, font_path: &'static str, size: i32, color: Color) -> Result<Sprite, AssetError> {
+        let font = self.assets.font(font_path, size)?;
+        let font_file = self.assets.path(font_path);
+        let corrupt = |e: String| AssetError::Corrupt(font_file.clone(), e);
+
+        let surface = font.render(text).blended(color)
+            .map_err(|e| corrupt(format!("{:?}", e)))?;
+
+        self.renderer.create_texture_from_surface(&surface)
+            .map(Sprite::new)
+            .map_err(|e| corrupt(format!("{:?}", e)))
+    }
+
+    /// Returns a seed for the random number generator of a new game. The
+    /// sequence of seeds only depends on `Config::seed`, so that a session
+    /// can be reproduced from it.
+    pub fn next_seed(&mut self) -> u64 {
+        self.seeds.gen()
     }
 
     /// Play a sound once, and allocate new channels if this is necessary.
@@ -87,24 +133,206 @@ impl<'window> Phi<'window> {
 /// A `ViewAction` is a way for the currently executed view to
 /// communicate with the game loop. It specifies which action
 /// should be executed before the next rendering.
+///
+/// Views are kept on a stack, of which only the top one is updated.
 pub enum ViewAction {
     None,
     Quit,
+
+    /// Replaces the current view.
     ChangeView(Box<View>),
+
+    /// Puts a view on top of the current one, which is kept as it is until
+    /// the new view is popped.
+    Push(Box<View>),
+
+    /// Removes the current view, returning to the one underneath. Popping the
+    /// last view quits the game.
+    Pop,
+
+    /// Removes every view, and starts over from this one.
+    Reset(Box<View>),
+
+    /// Some asset could not be loaded. Every view is removed, and the error
+    /// is shown to the player until they quit.
+    Fail(AssetError),
+}
+
+impl ViewAction {
+    /// Changes to the given view, unless it could not be created.
+    pub fn change<V: View + 'static>(view: Result<V, AssetError>) -> ViewAction {
+        match view {
+            Ok(view) => ViewAction::ChangeView(Box::new(view)),
+            Err(e) => ViewAction::Fail(e),
+        }
+    }
+
+    /// Pushes the given view, unless it could not be created.
+    pub fn push<V: View + 'static>(view: Result<V, AssetError>) -> ViewAction {
+        match view {
+            Ok(view) => ViewAction::Push(Box::new(view)),
+            Err(e) => ViewAction::Fail(e),
+        }
+    }
+
+    /// Starts over from the given view, unless it could not be created.
+    pub fn reset<V: View + 'static>(view: Result<V, AssetError>) -> ViewAction {
+        match view {
+            Ok(view) => ViewAction::Reset(Box::new(view)),
+            Err(e) => ViewAction::Fail(e),
+        }
+    }
 }
 
 
 pub trait View {
-    /// Called on every frame to take care of both the logic and
-    /// the rendering of the current view.
+    /// Called `Config::tick_rate` times every second to take care of the
+    /// logic of the current view.
+    ///
+    /// `dt` is always the same, and is expressed in seconds.
+    fn update(&mut self, context: &mut Phi, dt: f64) -> ViewAction;
+
+    /// Called on every frame to take care of the rendering of the current
+    /// view.
     ///
-    /// `elapsed` is expressed in seconds.
-    fn render(&mut self, context: &mut Phi, elapsed: f64) -> ViewAction;
+    /// `alpha`, in `[0, 1)`, is how far the game is between the last update
+    /// and the next one. Moving objects should be drawn that far between
+    /// their previous and current positions, so that motion stays smooth
+    /// whatever the display rate.
+    fn render(&mut self, context: &mut Phi, alpha: f64);
+
+    /// Whether the views underneath this one should be rendered before it,
+    /// as is the case of menus drawn over the game. Those views are not
+    /// updated, and are rendered as they were when this view was pushed.
+    fn is_overlay(&self) -> bool {
+        false
+    }
 }
 
 
-pub fn spawn<F>(title: &str, init: F)
-    where F: Fn(&mut Phi) -> Box<View> {
+/// The font in which `ErrorView` writes.
+const ERROR_FONT: &'static str = "belligerent.ttf";
+
+/// Tells the player why the game cannot go on, until they quit.
+struct ErrorView {
+    lines: Vec<Sprite>,
+}
+
+impl ErrorView {
+    /// The error is printed in any case. Returns `None` if it cannot be shown
+    /// as well, e.g. because the font is missing too.
+    fn new(phi: &mut Phi, error: AssetError) -> Option<ErrorView> {
+        println!("Error: {}", error);
+        ::sdl2_mixer::Music::halt();
+
+        let message = error.to_string();
+        let mut texts = vec![("Something went wrong", 32)];
+        texts.extend(message.lines().map(|line| (line, 18)));
+        texts.push(("Press any key to quit", 18));
+
+        let mut lines = vec![];
+        for (text, size) in texts {
+            lines.push(phi.ttf_str_sprite(text, ERROR_FONT, size, Color::RGB(255, 255, 255)).ok()?);
+        }
+
+        Some(ErrorView { lines: lines })
+    }
+}
+
+impl View for ErrorView {
+    fn update(&mut self, phi: &mut Phi, _: f64) -> ViewAction {
+        if phi.events.now.quit || phi.events.now.key_pressed.is_some() ||
+            phi.events.now.confirm == Some(true) || phi.events.now.mouse_left == Some(true) {
+            ViewAction::Quit
+        } else {
+            ViewAction::None
+        }
+    }
+
+    fn render(&mut self, phi: &mut Phi, _: f64) {
+        phi.renderer.set_draw_color(Color::RGB(0, 0, 0));
+        phi.renderer.clear();
+
+        // Center the lines in the window
+        let (win_w, win_h) = phi.output_size();
+        let total_h: f64 = self.lines.iter().map(|line| line.size().1 + 10.0).sum();
+        let mut y = (win_h - total_h) / 2.0;
+
+        for line in &self.lines {
+            let (w, h) = line.size();
+            phi.renderer.copy_sprite(line, Rectangle {
+                w: w,
+                h: h,
+                x: (win_w - w) / 2.0,
+                y: y,
+            });
+            y += h + 10.0;
+        }
+    }
+}
+
+
+/// Settings of the game loop run by `spawn`.
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// The number of times `View::update` is called every second.
+    pub tick_rate: f64,
+
+    /// The maximum number of updates which may be run before a frame is
+    /// rendered. If the game falls further behind than this, e.g. because
+    /// the window was dragged around, the extra time is dropped rather than
+    /// making every following frame slower to catch up.
+    pub max_catch_up: u32,
+
+    /// The seed from which every random decision is derived. If it is `None`,
+    /// then a random one is picked, and printed so that the run may be
+    /// reproduced.
+    pub seed: Option<u64>,
+
+    /// The number of ships the player starts every game with.
+    pub lives: u32,
+
+    /// The size, in logical pixels, of the screen in which the game takes
+    /// place. The window starts at this size and is scaled when resized.
+    pub resolution: (u32, u32),
+
+    /// If set, the input of every update is written to this replay file.
+    pub record: Option<PathBuf>,
+
+    /// If set, the input is read from this replay file instead of the
+    /// keyboard, and its seed, tick rate, lives and resolution override the
+    /// ones above. The game stops once the replay is over.
+    pub replay: Option<PathBuf>,
+
+    /// The file from which the key bindings are loaded, and to which they
+    /// are saved. If it is `None`, then `controls.cfg` in the user's
+    /// configuration directory is used.
+    pub controls: Option<PathBuf>,
+
+    /// The directory which holds the game's assets. If it is `None`, then it
+    /// is searched for, see `paths::assets_dir`.
+    pub assets: Option<PathBuf>,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            tick_rate: 60.0,
+            max_catch_up: 5,
+            seed: None,
+            lives: 3,
+            resolution: (800, 600),
+            record: None,
+            replay: None,
+            controls: None,
+            assets: None,
+        }
+    }
+}
+
+
+pub fn spawn<F>(title: &str, config: Config, init: F)
+    where F: Fn(&mut Phi) -> Result<Box<View>, AssetError> {
     // Initialize SDL2
     let sdl_context = ::sdl2::init().unwrap();
     let video = sdl_context.video().unwrap();
@@ -115,63 +343,184 @@ pub fn spawn<F>(title: &str, init: F)
     ::sdl2_mixer::open_audio(44100, ::sdl2_mixer::AUDIO_S16LSB, 2, 1024).unwrap();
     ::sdl2_mixer::allocate_channels(32);
 
+    // Load the replay first, as it determines the seed and tick rate.
+    let mut replay = match config.replay {
+        Some(ref path) => match Replay::load(path) {
+            Ok(replay) => Some(replay),
+            Err(e) => {
+                println!("Could not load the replay: {}", e);
+                return;
+            }
+        },
+        None => None,
+    };
+
+    let (seed, tick_rate, lives, resolution) = match replay {
+        Some(ref replay) => (replay.seed, replay.tick_rate, replay.lives, replay.resolution),
+        None => (config.seed.unwrap_or_else(::rand::random), config.tick_rate,
+                 config.lives, config.resolution),
+    };
+    println!("Seed: {}", seed);
+
     // Create the window
-    let window = video.window(title, 800, 600)
+    let window = video.window(title, resolution.0, resolution.1)
         .position_centered().opengl().resizable()
         .build().unwrap();
 
+    let mut recorder = match config.record {
+        Some(ref path) => match Recorder::create(path, seed, tick_rate, lives, resolution) {
+            Ok(recorder) => Some(recorder),
+            Err(e) => {
+                println!("Not recording: {}", e);
+                None
+            }
+        },
+        None => None,
+    };
+
+    // Controllers are opened as they are plugged in, including those which
+    // are already connected when the game starts.
+    let controllers = sdl_context.game_controller().unwrap();
+
+    let bindings = match config.controls.clone()
+        .or_else(|| paths::config_dir().map(|dir| dir.join(bindings::CONTROLS_FILE))) {
+        Some(path) => Bindings::load(&path),
+        None => Bindings::defaults(),
+    };
+
+    let assets_dir = match paths::assets_dir(config.assets.as_ref().map(|dir| dir.as_path())) {
+        Some(dir) => dir,
+        None => {
+            println!("Could not find the assets, set {} to where they are", paths::ASSETS_VAR);
+            PathBuf::from("assets")
+        }
+    };
+
     // Create the context
+    //? Presenting in sync with the display keeps us from rendering frames
+    //? which will never be shown.
     let mut context = Phi::new (
-        Events::new(sdl_context.event_pump().unwrap()),
-        window.renderer().accelerated().build().unwrap(),
-        ttf_context,
+        Events::new(sdl_context.event_pump().unwrap(), bindings, controllers),
+        window.renderer().accelerated().present_vsync().build().unwrap(),
+        Assets::new(ttf_context, assets_dir),
+        seed,
+        lives,
+        replay.is_some(),
+        resolution,
     );
 
     // Create the default view
-    let mut current_view = init(&mut context);
+    let mut views: Vec<Box<View>> = match init(&mut context) {
+        Ok(view) => vec![view],
+        Err(e) => match ErrorView::new(&mut context, e) {
+            Some(view) => vec![Box::new(view)],
+            None => return,
+        },
+    };
 
 
-    // Frame timing  使用的上一秒帧率
-
-    let interval = 1_000 / 60;//间隔 60帧，每帧多长时间？
+    // Frame timing
+    let dt = 1.0 / tick_rate;
+    let max_lag = dt * config.max_catch_up as f64;
     let mut before = timer.ticks();
-    let mut last_second = timer.ticks();
-    let mut fps = 0u16;
+    let mut lag = 0.0;
 
-    loop {
-        // Frame timing (bis)
+    // Whether the views changed since they were last rendered.
+    let mut dirty = true;
 
+    'running: loop {
         let now = timer.ticks();
-        let dt = now - before;
-        let elapsed = dt as f64 / 1_000.0;//过去的时间
+        lag += (now - before) as f64 / 1_000.0;
+        before = now;
 
-        // If the time elapsed since the last frame is too small, wait out the
-        // difference and try again.
-        if dt < interval {
-            timer.delay(interval - dt);
-            continue;
+        // Drop whatever time we could not catch up with.
+        if lag > max_lag {
+            lag = max_lag;
         }
 
-        before = now;
-        fps += 1;
+        // Logic
+        //? Events are pumped once per update. The first update of a frame
+        //? therefore receives everything that happened since the last one,
+        //? and a key press is never seen twice.
+        while lag >= dt {
+            let step = match replay {
+                Some(ref mut replay) => match replay.next_frame() {
+                    Some(frame) => {
+                        if context.events.discard_pending() {
+                            break 'running;
+                        }
 
-        if now - last_second > 1_000 {
-            //println!("FPS: {}", fps);
-            last_second = now;
-            fps = 0;
-        }
-        println!("{}", elapsed);
+                        if let Err(e) = context.events.apply_tokens(&frame.tokens) {
+                            println!("Corrupt replay: {}", e);
+                            break 'running;
+                        }
+
+                        frame.dt
+                    },
+                    None => {
+                        println!("The replay is over.");
+                        break 'running;
+                    }
+                },
+                None => {
+                    context.events.pump(&mut context.renderer);
+                    dt
+                }
+            };
+
+            if let Some(e) = recorder.as_mut().and_then(|r| r.record(step, &context.events).err()) {
+                println!("Stopped recording: {}", e);
+                recorder = None;
+            }
 
+            match views.last_mut().unwrap().update(&mut context, step) {
+                ViewAction::None => {},
+                ViewAction::Quit => break 'running,
+                ViewAction::ChangeView(new_view) =>
+                    *views.last_mut().unwrap() = new_view,
+                ViewAction::Push(new_view) =>
+                    views.push(new_view),
+                ViewAction::Pop => {
+                    views.pop();
+                    if views.is_empty() {
+                        break 'running;
+                    }
+                },
+                ViewAction::Reset(new_view) =>
+                    views = vec![new_view],
+                ViewAction::Fail(e) => match ErrorView::new(&mut context, e) {
+                    Some(view) => views = vec![Box::new(view)],
+                    None => break 'running,
+                },
+            }
 
-        // Logic & rendering
+            lag -= dt;
+            dirty = true;
+        }
 
-        context.events.pump(&mut context.renderer);
+        //? Without vsync, nothing slows the loop down between two updates.
+        //? Rather than drawing the same frame over and over, give the time
+        //? left until the next one back to the system.
+        if !dirty {
+            timer.delay(((dt - lag) * 1_000.0).ceil() as u32);
+            continue;
+        }
 
-        match current_view.render(&mut context, elapsed) {
-            ViewAction::None => context.renderer.present(),
-            ViewAction::Quit => break,
-            ViewAction::ChangeView(new_view) =>
-                current_view = new_view,
+        // Rendering
+        //? Start from the highest view which hides everything below it. The
+        //? views which are frozen under an overlay are drawn at their latest
+        //? state, so that they do not jitter back and forth.
+        let top = views.len() - 1;
+        let bottom = (0..top).rev()
+            .take_while(|&i| views[i + 1].is_overlay())
+            .last()
+            .unwrap_or(top);
+
+        for i in bottom..top {
+            views[i].render(&mut context, 1.0);
         }
+        views[top].render(&mut context, lag / dt);
+        context.renderer.present();
+        dirty = false;
     }
-}
\ No newline at end of file
+}
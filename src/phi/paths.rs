@@ -0,0 +1,77 @@
+//! Where the game finds its own files, and where it keeps the files which
+//! belong to the player rather than to the game itself: settings on one hand,
+//! and what they achieved on the other.
+
+use find_folder::Search;
+use std::env;
+use std::path::{Path, PathBuf};
+
+/// The name of the directory created for the game inside of the user's
+/// directories.
+const APP_DIR: &'static str = "shoot_asteroid";
+
+/// The name of the directory which holds the images, sounds and fonts.
+const ASSETS_DIR: &'static str = "assets";
+
+/// The environment variable which may point to the assets directory.
+pub const ASSETS_VAR: &'static str = "SHOOT_ASTEROID_ASSETS";
+
+/// How many parent directories are searched for the assets directory.
+const SEARCH_DEPTH: u8 = 3;
+
+/// Finds the directory which holds the game's assets, so that the game works
+/// whichever directory it is started from.
+///
+/// `dir`, e.g. given on the command line, is used if it is set. Then comes the
+/// `SHOOT_ASTEROID_ASSETS` environment variable. Otherwise, an `assets`
+/// directory is looked for next to the executable and in its parents, then
+/// in the current directory and its parents. Returns `None` if there is none.
+pub fn assets_dir(dir: Option<&Path>) -> Option<PathBuf> {
+    if let Some(dir) = dir {
+        return Some(dir.to_path_buf());
+    }
+
+    if let Some(dir) = env::var_os(ASSETS_VAR) {
+        return Some(PathBuf::from(dir));
+    }
+
+    let exe_dir = env::current_exe().ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf));
+
+    exe_dir.and_then(|dir| Search::Parents(SEARCH_DEPTH).of(dir).for_folder(ASSETS_DIR).ok())
+        .or_else(|| Search::Parents(SEARCH_DEPTH).for_folder(ASSETS_DIR).ok())
+}
+
+/// The directory in which the player's settings are stored, e.g.
+/// `~/.config/shoot_asteroid` on Linux. Returns `None` if the environment does
+/// not tell where it should be.
+pub fn config_dir() -> Option<PathBuf> {
+    let base =
+        if cfg!(windows) {
+            env::var_os("APPDATA").map(PathBuf::from)
+        } else if cfg!(target_os = "macos") {
+            env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"))
+        } else {
+            env::var_os("XDG_CONFIG_HOME").map(PathBuf::from)
+                .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        };
+
+    base.map(|dir| dir.join(APP_DIR))
+}
+
+/// The directory in which the player's data, such as high scores, is stored,
+/// e.g. `~/.local/share/shoot_asteroid` on Linux. Returns `None` if the
+/// environment does not tell where it should be.
+pub fn data_dir() -> Option<PathBuf> {
+    let base =
+        if cfg!(windows) {
+            env::var_os("APPDATA").map(PathBuf::from)
+        } else if cfg!(target_os = "macos") {
+            env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"))
+        } else {
+            env::var_os("XDG_DATA_HOME").map(PathBuf::from)
+                .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share")))
+        };
+
+    base.map(|dir| dir.join(APP_DIR))
+}
@@ -0,0 +1,221 @@
+//! Recording and playback of the input received by the game.
+//!
+//! A replay is a text file. Its first line names the format and its version,
+//! followed by the seed, tick rate, number of lives and resolution of the
+//! recorded session. Every other line describes one update: the time it lasted, in
+//! seconds, then the state of the input during it, as produced by
+//! `Events::to_tokens`.
+//!
+//! ```text
+//! shoot-asteroid-replay 6
+//! seed 1234
+//! tick_rate 60
+//! lives 3
+//! resolution 800x600
+//! 0.016666666666666666 mouse=400,300 move_up +fire
+//! ```
+
+use phi::Events;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+const MAGIC: &'static str = "shoot-asteroid-replay";
+
+/// The version of the format written by `Recorder`. Replays of any other
+/// version are refused.
+pub const VERSION: u32 = 6;
+
+/// The input recorded for a single update.
+pub struct ReplayFrame {
+    pub dt: f64,
+    pub tokens: Vec<String>,
+}
+
+/// Writes the input of every update to a replay file, as it is received.
+pub struct Recorder {
+    out: BufWriter<File>,
+}
+
+impl Recorder {
+    /// Creates the replay file at `path` and writes its header.
+    pub fn create(path: &Path, seed: u64, tick_rate: f64, lives: u32, resolution: (u32, u32)) -> Result<Recorder, String> {
+        let file = File::create(path)
+            .map_err(|e| format!("could not create {}: {}", path.display(), e))?;
+
+        let mut recorder = Recorder { out: BufWriter::new(file) };
+        recorder.write_line(&format!("{} {}\nseed {}\ntick_rate {}\nlives {}\nresolution {}x{}",
+                                     MAGIC, VERSION, seed, tick_rate, lives,
+                                     resolution.0, resolution.1))?;
+        Ok(recorder)
+    }
+
+    /// Appends the input of an update which lasted `dt` seconds.
+    pub fn record(&mut self, dt: f64, events: &Events) -> Result<(), String> {
+        let mut line = dt.to_string();
+
+        for token in events.to_tokens() {
+            line.push(' ');
+            line.push_str(&token);
+        }
+
+        self.write_line(&line)
+    }
+
+    fn write_line(&mut self, line: &str) -> Result<(), String> {
+        writeln!(self.out, "{}", line).map_err(|e| e.to_string())
+    }
+}
+
+/// A replay file loaded in memory, which hands out its frames in order.
+pub struct Replay {
+    pub seed: u64,
+    pub tick_rate: f64,
+    pub lives: u32,
+    pub resolution: (u32, u32),
+    frames: VecDeque<ReplayFrame>,
+}
+
+impl Replay {
+    /// Reads and validates the whole replay file at `path`.
+    pub fn load(path: &Path) -> Result<Replay, String> {
+        let file = File::open(path)
+            .map_err(|e| format!("could not open {}: {}", path.display(), e))?;
+
+        let mut lines = BufReader::new(file).lines().enumerate();
+        let mut next_line = || -> Result<Option<(usize, String)>, String> {
+            match lines.next() {
+                Some((i, Ok(line))) => Ok(Some((i + 1, line))),
+                Some((i, Err(e))) => Err(format!("line {}: {}", i + 1, e)),
+                None => Ok(None),
+            }
+        };
+
+        // Every header line is a name followed by a value.
+        let mut header = |name: &str| -> Result<String, String> {
+            match next_line()? {
+                Some((i, line)) => {
+                    let mut words = line.split_whitespace();
+                    match (words.next(), words.next()) {
+                        (Some(n), Some(value)) if n == name => Ok(value.to_string()),
+                        _ => Err(format!("line {}: expected `{} <value>`", i, name)),
+                    }
+                },
+                None => Err(format!("missing `{}` header", name)),
+            }
+        };
+
+        let version = header(MAGIC)
+            .map_err(|_| format!("{} is not a replay file", path.display()))?;
+
+        if version != VERSION.to_string() {
+            return Err(format!("unsupported replay version {} (expected {})", version, VERSION));
+        }
+
+        let seed = header("seed")?.parse()
+            .map_err(|_| "invalid seed".to_string())?;
+
+        //? A tick rate of 0 would make every update last forever.
+        let tick_rate = match header("tick_rate")?.parse::<f64>() {
+            Ok(rate) if rate.is_finite() && rate > 0.0 => rate,
+            _ => return Err("invalid tick rate".to_string()),
+        };
+
+        //? As with `--lives`, a game needs at least one ship.
+        let lives = match header("lives")?.parse::<u32>() {
+            Ok(lives) if lives > 0 => lives,
+            _ => return Err("invalid number of lives".to_string()),
+        };
+
+        let resolution = parse_resolution(&header("resolution")?)
+            .ok_or("invalid resolution".to_string())?;
+
+        let mut frames = VecDeque::new();
+
+        while let Some((i, line)) = next_line()? {
+            let mut words = line.split_whitespace();
+
+            let dt = match words.next().map(str::parse::<f64>) {
+                Some(Ok(dt)) if dt.is_finite() && dt > 0.0 => dt,
+                _ => return Err(format!("line {}: expected the positive duration of the update", i)),
+            };
+
+            frames.push_back(ReplayFrame {
+                dt: dt,
+                tokens: words.map(str::to_string).collect(),
+            });
+        }
+
+        Ok(Replay {
+            seed: seed,
+            tick_rate: tick_rate,
+            lives: lives,
+            resolution: resolution,
+            frames: frames,
+        })
+    }
+
+    /// Returns the input of the next update, or `None` once the replay is
+    /// over.
+    pub fn next_frame(&mut self) -> Option<ReplayFrame> {
+        self.frames.pop_front()
+    }
+}
+
+/// Reads a resolution such as `800x600`.
+pub fn parse_resolution(text: &str) -> Option<(u32, u32)> {
+    let mut parts = text.splitn(2, 'x');
+    match (parts.next().map(str::parse), parts.next().map(str::parse)) {
+        (Some(Ok(w)), Some(Ok(h))) if w > 0 && h > 0 => Some((w, h)),
+        _ => None,
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::Write;
+    use super::{Replay, MAGIC, VERSION};
+
+    /// Loads a replay whose header holds the given tick rate and number of
+    /// lives, followed by the given frames.
+    fn load(name: &str, tick_rate: &str, lives: &str, frames: &str) -> Result<Replay, String> {
+        let path = ::std::env::temp_dir().join(format!("replay-test-{}.txt", name));
+        write!(File::create(&path).unwrap(),
+               "{} {}\nseed 1\ntick_rate {}\nlives {}\nresolution 800x600\n{}",
+               MAGIC, VERSION, tick_rate, lives, frames).unwrap();
+
+        let replay = Replay::load(&path);
+        ::std::fs::remove_file(&path).unwrap();
+        replay
+    }
+
+    #[test]
+    fn loads_a_valid_replay() {
+        let replay = load("valid", "60", "3", "0.016 move_up\n0.016\n").unwrap();
+        assert_eq!(replay.tick_rate, 60.0);
+        assert_eq!(replay.lives, 3);
+    }
+
+    #[test]
+    fn rejects_games_without_lives() {
+        assert!(load("lives", "60", "0", "").is_err());
+    }
+
+    #[test]
+    fn rejects_tick_rates_which_are_not_positive() {
+        for rate in &["0", "-60", "inf", "NaN"] {
+            assert!(load("tick-rate", rate, "3", "").is_err(), "accepted {}", rate);
+        }
+    }
+
+    #[test]
+    fn rejects_durations_which_are_not_positive() {
+        for dt in &["0", "-0.016", "inf", "NaN"] {
+            let frames = format!("0.016\n{} move_up\n", dt);
+            assert!(load("duration", "60", "3", &frames).is_err(), "accepted {}", dt);
+        }
+    }
+}
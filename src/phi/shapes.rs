@@ -0,0 +1,369 @@
+//! Shapes with which entities collide, more accurate than their rectangles.
+//!
+//! Every entity type picks a `Hitbox`, which is placed over the rectangle of
+//! an entity to get the `Collider` it collides with. Two colliders are
+//! compared with the most accurate test they both support: exact geometry
+//! between shapes, or the solid pixels of the frames when masks are known.
+
+use phi::data::Rectangle;
+
+/// A region of the plane, in the same coordinates as `Rectangle`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Shape {
+    Rect(Rectangle),
+    Circle { center: (f64, f64), radius: f64 },
+
+    /// A box rotated clockwise by `angle` degrees around its center.
+    Oriented { center: (f64, f64), half_size: (f64, f64), angle: f64 },
+}
+
+impl Shape {
+    /// The smallest rectangle containing the shape.
+    pub fn bounds(&self) -> Rectangle {
+        match *self {
+            Shape::Rect(rect) => rect,
+            Shape::Circle { center, radius } =>
+                Rectangle::with_size(2.0 * radius, 2.0 * radius).center_at(center),
+            Shape::Oriented { center, half_size, angle } => {
+                let (sin, cos) = angle.to_radians().sin_cos();
+                let w = 2.0 * (half_size.0 * cos.abs() + half_size.1 * sin.abs());
+                let h = 2.0 * (half_size.0 * sin.abs() + half_size.1 * cos.abs());
+                Rectangle::with_size(w, h).center_at(center)
+            },
+        }
+    }
+
+    /// Whether the point `(x, y)` lies inside of the shape.
+    pub fn contains_point(&self, point: (f64, f64)) -> bool {
+        match *self {
+            Shape::Rect(rect) => rect.contains_point(point),
+            Shape::Circle { center, radius } =>
+                distance_sq(center, point) <= radius * radius,
+            Shape::Oriented { center, half_size, angle } => {
+                let (x, y) = to_local(point, center, angle);
+                x.abs() <= half_size.0 && y.abs() <= half_size.1
+            },
+        }
+    }
+
+    pub fn overlaps(&self, other: &Shape) -> bool {
+        match (*self, *other) {
+            (Shape::Rect(a), Shape::Rect(b)) => a.overlaps(b),
+
+            (Shape::Circle { center: c1, radius: r1 }, Shape::Circle { center: c2, radius: r2 }) =>
+                distance_sq(c1, c2) < (r1 + r2) * (r1 + r2),
+
+            (Shape::Circle { center, radius }, box_shape) |
+            (box_shape, Shape::Circle { center, radius }) => {
+                //? In the frame of the box, it is enough to find the point of
+                //? the box which is the closest to the center of the circle.
+                let (box_center, half_size, angle) = box_shape.as_oriented();
+                let (x, y) = to_local(center, box_center, angle);
+                let closest = (x.max(-half_size.0).min(half_size.0),
+                               y.max(-half_size.1).min(half_size.1));
+                distance_sq((x, y), closest) < radius * radius
+            },
+
+            (a, b) => boxes_overlap(a.as_oriented(), b.as_oriented()),
+        }
+    }
+
+    /// The corners of a rectangle or oriented box, clockwise from the top-left
+    /// one before it is rotated.
+    pub fn corners(&self) -> Vec<(f64, f64)> {
+        corners(self.as_oriented())
+    }
+
+    /// The center, half size and angle of a rectangle or oriented box.
+    fn as_oriented(&self) -> ((f64, f64), (f64, f64), f64) {
+        match *self {
+            Shape::Rect(rect) => (rect.center(), (rect.w / 2.0, rect.h / 2.0), 0.0),
+            Shape::Oriented { center, half_size, angle } => (center, half_size, angle),
+            Shape::Circle { center, radius } => (center, (radius, radius), 0.0),
+        }
+    }
+}
+
+fn distance_sq(a: (f64, f64), b: (f64, f64)) -> f64 {
+    (a.0 - b.0) * (a.0 - b.0) + (a.1 - b.1) * (a.1 - b.1)
+}
+
+/// The coordinates of `point` in the frame of a box centered on `center` and
+/// rotated clockwise by `angle` degrees.
+fn to_local(point: (f64, f64), center: (f64, f64), angle: f64) -> (f64, f64) {
+    let (sin, cos) = angle.to_radians().sin_cos();
+    let (dx, dy) = (point.0 - center.0, point.1 - center.1);
+    (dx * cos + dy * sin, -dx * sin + dy * cos)
+}
+
+/// The corners of the box centered on `center`, of the given half size, and
+/// rotated clockwise by `angle` degrees.
+fn corners((center, half, angle): ((f64, f64), (f64, f64), f64)) -> Vec<(f64, f64)> {
+    let (sin, cos) = angle.to_radians().sin_cos();
+    [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)].iter()
+        .map(|&(sx, sy)| {
+            let (x, y) = (sx * half.0, sy * half.1);
+            (center.0 + x * cos - y * sin, center.1 + x * sin + y * cos)
+        })
+        .collect()
+}
+
+/// Whether two oriented boxes overlap.
+//? By the separating axis theorem, two convex shapes are apart if and only if
+//? their projections are apart on some axis. For two boxes, the axes along
+//? their sides are the only ones which need to be tried.
+fn boxes_overlap(a: ((f64, f64), (f64, f64), f64), b: ((f64, f64), (f64, f64), f64)) -> bool {
+    let (corners_a, corners_b) = (corners(a), corners(b));
+
+    for &angle in &[a.2, a.2 + 90.0, b.2, b.2 + 90.0] {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        let project = |corners: &[(f64, f64)]| {
+            let dots: Vec<_> = corners.iter().map(|&(x, y)| x * cos + y * sin).collect();
+            (dots.iter().cloned().fold(::std::f64::INFINITY, f64::min),
+             dots.iter().cloned().fold(::std::f64::NEG_INFINITY, f64::max))
+        };
+
+        let ((min_a, max_a), (min_b, max_b)) = (project(&corners_a), project(&corners_b));
+        if max_a <= min_b || max_b <= min_a {
+            return false;
+        }
+    }
+
+    true
+}
+
+
+/// The pixels whose alpha is at least this are solid.
+const ALPHA_THRESHOLD: u8 = 128;
+
+/// Which pixels of a frame are solid, i.e. opaque enough to be hit.
+#[derive(Clone, Debug)]
+pub struct Mask {
+    w: usize,
+    h: usize,
+    solid: Vec<bool>,
+}
+
+impl Mask {
+    /// Cuts the mask of the region `src` out of an image of width `image_w`,
+    /// whose pixels have the given `alpha` values, row by row.
+    pub fn from_alpha(alpha: &[u8], image_w: usize, src: Rectangle) -> Mask {
+        let (x0, y0) = (src.x as usize, src.y as usize);
+        let (w, h) = (src.w as usize, src.h as usize);
+        let mut solid = Vec::with_capacity(w * h);
+
+        for y in y0..y0 + h {
+            for x in x0..x0 + w {
+                solid.push(alpha[y * image_w + x] >= ALPHA_THRESHOLD);
+            }
+        }
+
+        Mask { w: w, h: h, solid: solid }
+    }
+
+    /// Whether the mask, stretched over `rect`, is solid at `point`.
+    pub fn solid_at(&self, rect: Rectangle, point: (f64, f64)) -> bool {
+        if !rect.contains_point(point) {
+            return false;
+        }
+
+        let x = ((point.0 - rect.x) / rect.w * self.w as f64) as usize;
+        let y = ((point.1 - rect.y) / rect.h * self.h as f64) as usize;
+        x < self.w && y < self.h && self.solid[y * self.w + x]
+    }
+}
+
+/// Calls `test` on the center of every pixel of the screen which lies in both
+/// `a` and `b`, until it returns true.
+fn any_pixel<F: Fn((f64, f64)) -> bool>(a: Rectangle, b: Rectangle, test: F) -> bool {
+    let left = a.x.max(b.x).floor();
+    let top = a.y.max(b.y).floor();
+    let right = (a.x + a.w).min(b.x + b.w);
+    let bottom = (a.y + a.h).min(b.y + b.h);
+
+    let mut y = top;
+    while y < bottom {
+        let mut x = left;
+        while x < right {
+            if test((x + 0.5, y + 0.5)) {
+                return true;
+            }
+            x += 1.0;
+        }
+        y += 1.0;
+    }
+
+    false
+}
+
+
+/// How the shape of an entity is derived from its rectangle.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Hitbox {
+    /// The whole rectangle.
+    Rect,
+
+    /// A circle at the center of the rectangle, whose diameter is this
+    /// fraction of its smallest side.
+    Circle(f64),
+
+    /// A box at the center of the rectangle, whose sides are these fractions
+    /// of the rectangle's, rotated clockwise by `angle` degrees.
+    Oriented { scale: (f64, f64), angle: f64 },
+
+    /// The solid pixels of the current frame of the entity. This falls back
+    /// to the whole rectangle when the masks of the frames are unknown.
+    Mask,
+}
+
+impl Hitbox {
+    /// The same hitbox, for an entity turned clockwise by `angle` degrees.
+    /// Circles need not turn, and rectangles become boxes of the same size.
+    //? Masks are stretched over the rectangle, which cannot turn, so turned
+    //? entities collide with the box around their pixels instead.
+    pub fn rotated(self, angle: f64) -> Hitbox {
+        match self {
+            Hitbox::Circle(scale) => Hitbox::Circle(scale),
+            Hitbox::Oriented { scale, .. } => Hitbox::Oriented { scale: scale, angle: angle },
+            Hitbox::Rect | Hitbox::Mask => Hitbox::Oriented { scale: (1.0, 1.0), angle: angle },
+        }
+    }
+
+    /// The collider of an entity occupying `rect`, whose current frame has
+    /// the given `mask`, if known.
+    pub fn collider<'a>(&self, rect: Rectangle, mask: Option<&'a Mask>) -> Collider<'a> {
+        let shape = match *self {
+            Hitbox::Rect | Hitbox::Mask => Shape::Rect(rect),
+            Hitbox::Circle(scale) => Shape::Circle {
+                center: rect.center(),
+                radius: rect.w.min(rect.h) * scale / 2.0,
+            },
+            Hitbox::Oriented { scale, angle } => Shape::Oriented {
+                center: rect.center(),
+                half_size: (rect.w * scale.0 / 2.0, rect.h * scale.1 / 2.0),
+                angle: angle,
+            },
+        };
+
+        Collider {
+            rect: rect,
+            shape: shape,
+            mask: if *self == Hitbox::Mask { mask } else { None },
+        }
+    }
+}
+
+/// What an entity collides with, at some point in time.
+pub struct Collider<'a> {
+    /// Where the entity is drawn, over which its mask is stretched.
+    pub rect: Rectangle,
+    pub shape: Shape,
+    pub mask: Option<&'a Mask>,
+}
+
+impl<'a> Collider<'a> {
+    pub fn overlaps(&self, other: &Collider) -> bool {
+        //? The bounds are compared first, as this is cheap and rules out
+        //? most pairs.
+        let (bounds, other_bounds) = (self.shape.bounds(), other.shape.bounds());
+        if !bounds.overlaps(other_bounds) {
+            return false;
+        }
+
+        match (self.mask, other.mask) {
+            (None, None) => self.shape.overlaps(&other.shape),
+
+            (Some(mask), None) => any_pixel(bounds, other_bounds, |point| {
+                mask.solid_at(self.rect, point) && other.shape.contains_point(point)
+            }),
+
+            (None, Some(other_mask)) => any_pixel(bounds, other_bounds, |point| {
+                self.shape.contains_point(point) && other_mask.solid_at(other.rect, point)
+            }),
+
+            (Some(mask), Some(other_mask)) => any_pixel(bounds, other_bounds, |point| {
+                mask.solid_at(self.rect, point) && other_mask.solid_at(other.rect, point)
+            }),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use phi::data::Rectangle;
+    use super::{Hitbox, Mask, Shape};
+
+    fn circle(x: f64, y: f64, radius: f64) -> Shape {
+        Shape::Circle { center: (x, y), radius: radius }
+    }
+
+    /// A square of side 2 * `half`, turned by 45 degrees into a diamond.
+    fn diamond(x: f64, y: f64, half: f64) -> Shape {
+        Shape::Oriented { center: (x, y), half_size: (half, half), angle: 45.0 }
+    }
+
+    #[test]
+    fn circles() {
+        assert!(circle(0.0, 0.0, 5.0).overlaps(&circle(8.0, 0.0, 4.0)));
+        assert!(!circle(0.0, 0.0, 5.0).overlaps(&circle(10.0, 0.0, 4.0)));
+    }
+
+    #[test]
+    fn circle_and_oriented_box() {
+        // Their bounds overlap, but the circle is off the side of the diamond.
+        assert!(circle(12.0, 12.0, 3.0).bounds().overlaps(diamond(0.0, 0.0, 10.0).bounds()));
+        assert!(!circle(12.0, 12.0, 3.0).overlaps(&diamond(0.0, 0.0, 10.0)));
+
+        // Here, it touches one of its corners.
+        assert!(circle(0.0, 16.0, 3.0).overlaps(&diamond(0.0, 0.0, 10.0)));
+        assert!(diamond(0.0, 0.0, 10.0).overlaps(&circle(0.0, 16.0, 3.0)));
+    }
+
+    #[test]
+    fn boxes() {
+        let rect = Shape::Rect(Rectangle::with_size(10.0, 10.0));
+
+        // Off the corner of the rectangle, within its bounds
+        assert!(rect.bounds().overlaps(diamond(13.0, 13.0, 3.0).bounds()));
+        assert!(!rect.overlaps(&diamond(13.0, 13.0, 3.0)));
+        assert!(rect.overlaps(&diamond(12.0, 5.0, 3.0)));
+
+        assert!(diamond(0.0, 0.0, 3.0).overlaps(&diamond(5.0, 0.0, 3.0)));
+        assert!(!diamond(0.0, 0.0, 3.0).overlaps(&diamond(9.0, 0.0, 3.0)));
+    }
+
+    /// A mask of 4x4 pixels, whose left or right half is solid.
+    fn half_mask(left: bool) -> Mask {
+        let alpha: Vec<u8> = (0..16)
+            .map(|i| if (i % 4 < 2) == left { 255 } else { 0 })
+            .collect();
+        Mask::from_alpha(&alpha, 4, Rectangle::with_size(4.0, 4.0))
+    }
+
+    #[test]
+    fn masks() {
+        let area = Rectangle::with_size(40.0, 40.0);
+        let (left, right) = (half_mask(true), half_mask(false));
+        let masked = Hitbox::Mask.collider(area, Some(&left));
+
+        let on_the_left = Rectangle { x: 5.0, y: 10.0, w: 10.0, h: 10.0 };
+        let on_the_right = Rectangle { x: 25.0, ..on_the_left };
+        assert!(masked.overlaps(&Hitbox::Rect.collider(on_the_left, None)));
+        assert!(!masked.overlaps(&Hitbox::Rect.collider(on_the_right, None)));
+        assert!(!Hitbox::Rect.collider(on_the_right, None).overlaps(&masked));
+
+        // The solid halves of both masks are side by side, until one moves.
+        assert!(!masked.overlaps(&Hitbox::Mask.collider(area, Some(&right))));
+        let moved = Rectangle { x: -10.0, ..area };
+        assert!(masked.overlaps(&Hitbox::Mask.collider(moved, Some(&right))));
+    }
+
+    #[test]
+    fn rotated_hitboxes_keep_their_kind_and_scale() {
+        let scaled = Hitbox::Oriented { scale: (0.5, 0.8), angle: 0.0 };
+        assert_eq!(scaled.rotated(30.0), Hitbox::Oriented { scale: (0.5, 0.8), angle: 30.0 });
+        assert_eq!(Hitbox::Rect.rotated(30.0), Hitbox::Oriented { scale: (1.0, 1.0), angle: 30.0 });
+        assert_eq!(Hitbox::Circle(0.8).rotated(30.0), Hitbox::Circle(0.8));
+    }
+}
@@ -0,0 +1,169 @@
+//! Descriptions of spritesheets, read from text files kept next to their
+//! images, so that the art may be changed or retimed without recompiling.
+//!
+//! Every line is a property followed by its value. Lines starting with `#`
+//! are ignored. Frames are numbered from left to right, then from top to
+//! bottom, starting at 0, and named animations span a range of them.
+//!
+//! ```text
+//! image = explosion.png
+//! frame = 96x96
+//! grid = 5x4
+//! frames = 17
+//! fps = 16
+//! animation explode = 0-16
+//! ```
+
+use phi::gfx::{Animation, AnimatedSpriteDescr};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::Path;
+use std::str::FromStr;
+
+pub struct Spritesheet {
+    /// The name of the image holding the frames, relative to the assets
+    /// directory.
+    pub image_path: String,
+
+    pub frame_w: f64,
+    pub frame_h: f64,
+    pub frames_wide: usize,
+    pub frames_high: usize,
+    pub total_frames: usize,
+
+    /// How many frames the animations go through every second. A value of 0
+    /// means that the frames are not meant to be animated, and freezes them.
+    pub fps: f64,
+
+    animations: HashMap<String, Range<usize>>,
+}
+
+impl Spritesheet {
+    /// Reads the description of a spritesheet. Its image is looked for in the
+    /// directory of `sheet_path`, which is itself relative to the assets
+    /// directory.
+    pub fn parse(sheet_path: &str, contents: &str) -> Result<Spritesheet, String> {
+        let mut image = None;
+        let mut frame = None;
+        let mut grid = None;
+        let mut total = None;
+        let mut fps = 0.0;
+        let mut animations = HashMap::new();
+
+        for (i, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut parts = line.splitn(2, '=');
+            let key = parts.next().unwrap_or("").trim();
+            let value = parts.next()
+                .ok_or(format!("line {}: expected `{} = <value>`", i + 1, key))?
+                .trim();
+
+            let mut words = key.split_whitespace();
+            let result = match (words.next(), words.next(), words.next()) {
+                (Some("image"), None, _) => { image = Some(value.to_string()); Ok(()) },
+                (Some("frame"), None, _) => parse_pair(value).map(|(w, h)| frame = Some((w, h))),
+                (Some("grid"), None, _) => parse_pair(value).map(|(w, h)| grid = Some((w, h))),
+                (Some("frames"), None, _) => parse_number(value).map(|n| total = Some(n)),
+                (Some("fps"), None, _) => parse_number(value).map(|n| fps = n),
+                (Some("animation"), Some(name), None) => parse_range(value)
+                    .map(|range| { animations.insert(name.to_string(), range); }),
+                _ => Err(format!("unknown property `{}`", key)),
+            };
+
+            result.map_err(|e| format!("line {}: {}", i + 1, e))?;
+        }
+
+        let image = image.ok_or("missing `image`".to_string())?;
+        let (frame_w, frame_h) = frame.ok_or("missing `frame`".to_string())?;
+        let (frames_wide, frames_high) = grid.ok_or("missing `grid`".to_string())?;
+        //? Most sheets fill their whole grid, so the number of frames may be
+        //? left out.
+        let total_frames = total.unwrap_or(frames_wide * frames_high);
+
+        if fps < 0.0 {
+            return Err("`fps` may not be negative".to_string());
+        }
+
+        for (name, range) in &animations {
+            if range.end > total_frames {
+                return Err(format!("animation `{}` goes past the last of the {} frames",
+                                   name, total_frames));
+            }
+        }
+
+        let image_path = match Path::new(sheet_path).parent() {
+            Some(dir) => dir.join(image).to_string_lossy().into_owned(),
+            None => image,
+        };
+
+        Ok(Spritesheet {
+            image_path: image_path,
+            frame_w: frame_w,
+            frame_h: frame_h,
+            frames_wide: frames_wide,
+            frames_high: frames_high,
+            total_frames: total_frames,
+            fps: fps,
+            animations: animations,
+        })
+    }
+
+    /// How to cut the image into frames, for `gfx::load_frames`.
+    pub fn descr(&self) -> AnimatedSpriteDescr {
+        AnimatedSpriteDescr {
+            image_path: &self.image_path,
+            total_frames: self.total_frames,
+            frames_high: self.frames_high,
+            frames_wide: self.frames_wide,
+            frame_w: self.frame_w,
+            frame_h: self.frame_h,
+        }
+    }
+
+    /// The frames of the animation called `name`.
+    pub fn range(&self, name: &str) -> Result<Range<usize>, String> {
+        self.animations.get(name).cloned()
+            .ok_or(format!("no animation called `{}`", name))
+    }
+
+    /// The playback of the animation called `name`, at the speed of the
+    /// sheet.
+    pub fn animation(&self, name: &str) -> Result<Animation, String> {
+        let range = self.range(name)?;
+        Ok(Animation::with_fps(range.end - range.start, self.fps))
+    }
+}
+
+fn parse_number<T: FromStr>(value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("invalid number `{}`", value))
+}
+
+/// Reads a value such as `96x96`.
+fn parse_pair<T: FromStr>(value: &str) -> Result<(T, T), String> {
+    let mut parts = value.splitn(2, 'x');
+    match (parts.next(), parts.next()) {
+        (Some(w), Some(h)) => Ok((parse_number(w.trim())?, parse_number(h.trim())?)),
+        _ => Err(format!("expected `<width>x<height>`, got `{}`", value)),
+    }
+}
+
+/// Reads a value such as `0-16`, both ends included, or `4` for a single
+/// frame.
+fn parse_range(value: &str) -> Result<Range<usize>, String> {
+    let mut parts = value.splitn(2, '-');
+    let first = parts.next().unwrap_or("").trim();
+    let last = parts.next().unwrap_or(first).trim();
+
+    let first: usize = parse_number(first)?;
+    let last: usize = parse_number(last)?;
+
+    if last < first {
+        return Err(format!("the range {}-{} is backwards", first, last));
+    }
+
+    Ok(first..last + 1)
+}
@@ -1,5 +1,5 @@
 use phi::data::Rectangle;
-use phi::Phi;
+use phi::ecs::{Body, Look};
 use sdl2::pixels::Color;
 
 /// Pixels traveled horizontally by a bullet every second.
@@ -7,85 +7,219 @@ const BULLET_SPEED: f64 = 240.0;
 const BULLET_W: f64 = 8.0;
 const BULLET_H: f64 = 4.0;
 
+/// How far, in pixels, sine-wave bullets stray from the line they follow,
+/// and how many times per second they go up and down.
+const WAVE_AMPLITUDE: f64 = 24.0;
+const WAVE_FREQUENCY: f64 = 2.0;
 
+/// The angles, in degrees and clockwise, at which the spread is fired.
+const SPREAD_ANGLES: [f64; 3] = [-30.0, 0.0, 30.0];
+
+/// Piercing bullets are longer and faster than the others.
+const PIERCING_SPEED: f64 = 360.0;
+const PIERCING_W: f64 = 20.0;
+const PIERCING_H: f64 = 4.0;
+
+
+/// How a bullet behaves, once it has been fired. Where it is, how it is
+/// drawn and when it leaves the game are the components of its entity.
 pub trait Bullet {
-    /// Update the bullet.
-    /// If the bullet should be destroyed, e.g. because it has left the screen,
-    /// then return `None`.
-    /// Otherwise, return `Some(update_bullet)`.
-    fn update(self: Box<Self>, phi: &mut Phi, dt: f64) -> Option<Box<Bullet>>;
+    /// Move the bullet, whose position is `body`, by `dt` seconds.
+    fn update(&mut self, body: &mut Body, dt: f64);
 
-    /// Render the bullet to the screen.
-    fn render(&self, phi: &mut Phi);
+    /// How the bullet is drawn.
+    fn look(&self) -> Look;
 
-    /// Return the bullet's bounding box.
-    fn rect(&self) -> Rectangle;
+    /// Whether the bullet goes on after hitting something, rather than being
+    /// destroyed with it.
+    fn pierces(&self) -> bool {
+        false
+    }
+
+    /// The direction in which the bullet flies, in degrees clockwise from
+    /// the right. The bullet is drawn and collides turned that way.
+    fn angle(&self) -> f64 {
+        0.0
+    }
 }
 
+/// Flies straight at a constant speed.
 #[derive(Clone, Copy)]
 struct RectBullet {
-    rect: Rectangle,
+    /// Pixels traveled every second, horizontally and vertically.
+    vel: (f64, f64),
+    angle: f64,
+    color: Color,
 }
 
-impl Bullet for RectBullet {
-    /// Update the bullet.
-    /// If the bullet should be destroyed, e.g. because it has left the screen,
-    /// then return `None`.
-    /// Otherwise, return `Some(update_bullet)`.
-    fn update(mut self: Box<Self>, phi: &mut Phi, dt: f64) -> Option<Box<Bullet>> {
-        let (w, _) = phi.output_size();
-        self.rect.x += BULLET_SPEED * dt;
-
-        // If the bullet has left the screen, then delete it.
-        if self.rect.x > w {
-            None
-        } else {
-            Some(self)
+impl RectBullet {
+    /// A bullet fired at `angle` degrees from the right, clockwise.
+    fn at_angle(angle: f64, color: Color) -> RectBullet {
+        let (sin, cos) = angle.to_radians().sin_cos();
+        RectBullet {
+            vel: (BULLET_SPEED * cos, BULLET_SPEED * sin),
+            angle: angle,
+            color: color,
         }
     }
+}
+
+impl Bullet for RectBullet {
+    fn update(&mut self, body: &mut Body, dt: f64) {
+        body.prev = body.rect;
+        body.rect.x += self.vel.0 * dt;
+        body.rect.y += self.vel.1 * dt;
+    }
 
-    /// Render the bullet to the screen.
-    fn render(&self, phi: &mut Phi) {
-        // We will render this kind of bullet in yellow.
-        //? This is exactly how we drew our first moving rectangle in the
-        //? seventh part of this series.
-        phi.renderer.set_draw_color(Color::RGB(230, 230, 30));
-        phi.renderer.fill_rect(self.rect.to_sdl().unwrap());
+    fn look(&self) -> Look {
+        Look::Fill(self.color)
     }
 
-    /// Return the bullet's bounding box.
-    fn rect(&self) -> Rectangle {
-        self.rect
+    fn angle(&self) -> f64 {
+        self.angle
     }
 }
 
-pub fn spawn_bullets( cannons_x: f64,
+/// Weaves up and down around the line from which it was fired.
+#[derive(Clone, Copy)]
+struct SineBullet {
+    /// The height around which the bullet oscillates.
+    origin_y: f64,
+
+    /// Seconds since the bullet was fired.
+    elapsed: f64,
+
+    /// Whether the bullet starts by going down rather than up, so that pairs
+    /// of bullets cross each other.
+    mirrored: bool,
+}
+
+impl Bullet for SineBullet {
+    fn update(&mut self, body: &mut Body, dt: f64) {
+        body.prev = body.rect;
+        self.elapsed += dt;
+
+        let offset = WAVE_AMPLITUDE *
+            (self.elapsed * WAVE_FREQUENCY * 2.0 * ::std::f64::consts::PI).sin();
+
+        body.rect.x += BULLET_SPEED * dt;
+        body.rect.y = self.origin_y + if self.mirrored { offset } else { -offset };
+    }
+
+    fn look(&self) -> Look {
+        Look::Fill(Color::RGB(80, 230, 120))
+    }
+}
+
+/// Goes through everything in its way.
+#[derive(Clone, Copy)]
+struct PiercingBullet;
+
+impl Bullet for PiercingBullet {
+    fn update(&mut self, body: &mut Body, dt: f64) {
+        body.prev = body.rect;
+        body.rect.x += PIERCING_SPEED * dt;
+    }
+
+    fn look(&self) -> Look {
+        Look::Fill(Color::RGB(120, 200, 255))
+    }
+
+    fn pierces(&self) -> bool {
+        true
+    }
+}
+
+
+/// What the ship fires.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Weapon {
+    /// One bullet from each cannon, flying straight.
+    Twin,
+
+    /// One bullet from each cannon, weaving in opposite directions.
+    Wave,
+
+    /// Three bullets from between the cannons, fanning out.
+    Spread,
+
+    /// A single bullet which is not stopped by what it hits.
+    Piercing,
+}
+
+impl Weapon {
+    /// Every weapon, in the order in which the player goes through them.
+    pub fn all() -> Vec<Weapon> {
+        vec![Weapon::Twin, Weapon::Wave, Weapon::Spread, Weapon::Piercing]
+    }
+
+    /// The weapon after this one, coming back to the first after the last.
+    pub fn next(self) -> Weapon {
+        let all = Weapon::all();
+        let index = all.iter().position(|&weapon| weapon == self).unwrap();
+        all[(index + 1) % all.len()]
+    }
+
+    /// The name shown to the player.
+    pub fn name(self) -> &'static str {
+        match self {
+            Weapon::Twin => "Twin",
+            Weapon::Wave => "Wave",
+            Weapon::Spread => "Spread",
+            Weapon::Piercing => "Piercing",
+        }
+    }
+}
+
+/// The bullets fired by the cannons with `weapon`, and where each of them
+/// starts.
+pub fn spawn_bullets( weapon: Weapon,
+                      cannons_x: f64,
                       cannon1_y: f64,
-                      cannon2_y: f64) -> Vec<Box<Bullet>> {
-    let cannons_x = cannons_x;
-    let cannon1_y = cannon1_y;
-    let cannon2_y = cannon2_y;
-
-    // One bullet at the tip of every cannon
-    //? We could modify the initial position of the bullets by matching on
-    //? `self.current : ShipFrame`, however there is not much point to this
-    //? pedagogy-wise. You can try it out if you want. ;)
-    vec![
-            Box::new(RectBullet {
-                rect: Rectangle {
-                    x: cannons_x,
-                    y: cannon1_y,
-                    w: BULLET_W,
-                    h: BULLET_H,
-                }
-            }),
-            Box::new(RectBullet {
-                rect: Rectangle {
-                    x: cannons_x,
-                    y: cannon2_y,
-                    w: BULLET_W,
-                    h: BULLET_H,
-                }
-            })
-        ]
-}
\ No newline at end of file
+                      cannon2_y: f64) -> Vec<(Rectangle, Box<Bullet>)> {
+    let bullet_at = |y: f64| Rectangle {
+        x: cannons_x,
+        y: y,
+        w: BULLET_W,
+        h: BULLET_H,
+    };
+
+    let yellow = Color::RGB(230, 230, 30);
+
+    match weapon {
+        // One bullet at the tip of every cannon
+        //? We could modify the initial position of the bullets by matching on
+        //? `self.current : ShipFrame`, however there is not much point to this
+        //? pedagogy-wise. You can try it out if you want. ;)
+        Weapon::Twin => vec![
+            (bullet_at(cannon1_y), Box::new(RectBullet::at_angle(0.0, yellow)) as Box<Bullet>),
+            (bullet_at(cannon2_y), Box::new(RectBullet::at_angle(0.0, yellow))),
+        ],
+
+        Weapon::Wave => vec![
+            (bullet_at(cannon1_y),
+             Box::new(SineBullet { origin_y: cannon1_y, elapsed: 0.0, mirrored: false }) as Box<Bullet>),
+            (bullet_at(cannon2_y),
+             Box::new(SineBullet { origin_y: cannon2_y, elapsed: 0.0, mirrored: true })),
+        ],
+
+        Weapon::Spread => {
+            let middle_y = (cannon1_y + cannon2_y) / 2.0;
+            SPREAD_ANGLES.iter()
+                .map(|&angle| {
+                    let bullet: Box<Bullet> = Box::new(RectBullet::at_angle(angle, Color::RGB(255, 150, 40)));
+                    (bullet_at(middle_y), bullet)
+                })
+                .collect()
+        },
+
+        Weapon::Piercing => vec![
+            (Rectangle {
+                x: cannons_x,
+                y: (cannon1_y + cannon2_y) / 2.0,
+                w: PIERCING_W,
+                h: PIERCING_H,
+            }, Box::new(PiercingBullet) as Box<Bullet>),
+        ],
+    }
+}
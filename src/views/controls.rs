@@ -0,0 +1,202 @@
+use phi::{Action, Phi, View, ViewAction};
+use phi::assets::AssetError;
+use phi::data::Rectangle;
+use phi::gfx::{CopySprite, Sprite};
+use sdl2::keyboard::Keycode;
+use sdl2::pixels::Color;
+use views::shared::BgSet;
+
+pub const LABEL_FONT: &'static str = "belligerent.ttf";
+
+/// Lets the player choose which keys trigger which actions.
+///
+/// Confirming an action waits for a key: pressing a key which is not bound to
+/// the action binds it, pressing one which is unbinds it, `Backspace` restores
+/// the default keys and `Escape` cancels, as do the buttons of a controller
+/// which go back or confirm. The bindings are saved after every change.
+///
+/// Going back returns to the main menu, or to the view underneath if this one
+/// was pushed on top of it.
+pub struct ControlsView {
+    actions: Vec<Action>,
+
+    /// The sprites rendered for every action, when it is not and when it is
+    /// selected, respectively.
+    labels: Vec<(Sprite, Sprite)>,
+
+    selected: usize,
+
+    /// Whether the next key pressed goes to the selected action.
+    listening: bool,
+
+    /// Whether the view was pushed on top of another one.
+    nested: bool,
+
+    bg: BgSet,
+}
+
+impl ControlsView {
+    pub fn with_backgrounds(phi: &mut Phi, bg: BgSet) -> Result<ControlsView, AssetError> {
+        let mut view = ControlsView {
+            actions: Action::all(),
+            labels: vec![],
+            selected: 0,
+            listening: false,
+            nested: false,
+            bg: bg,
+        };
+
+        view.refresh_labels(phi)?;
+        Ok(view)
+    }
+
+    /// Creates a view which should be pushed on top of the current one, and
+    /// pops itself when the player goes back.
+    pub fn nested(phi: &mut Phi, bg: BgSet) -> Result<ControlsView, AssetError> {
+        let mut view = ControlsView::with_backgrounds(phi, bg)?;
+        view.nested = true;
+        Ok(view)
+    }
+
+    /// Renders the labels again, e.g. because the bindings changed.
+    fn refresh_labels(&mut self, phi: &mut Phi) -> Result<(), AssetError> {
+        let mut labels = Vec::with_capacity(self.actions.len());
+
+        for (i, &action) in self.actions.iter().enumerate() {
+            let text =
+                if self.listening && i == self.selected {
+                    format!("{}: press a key", action.name())
+                } else {
+                    let keys: Vec<_> = phi.events.bindings.keys(action).iter()
+                        .map(|key| key.name())
+                        .collect();
+                    format!("{}: {}", action.name(), keys.join(", "))
+                };
+
+            labels.push((
+                phi.ttf_str_sprite(&text, LABEL_FONT, 24, Color::RGB(220, 220, 220))?,
+                phi.ttf_str_sprite(&text, LABEL_FONT, 28, Color::RGB(255, 255, 255))?,
+            ));
+        }
+
+        self.labels = labels;
+        Ok(())
+    }
+
+    /// Binds or unbinds the key which was just pressed, if any.
+    fn listen(&mut self, phi: &mut Phi) -> Result<(), AssetError> {
+        let key = match phi.events.now.key_pressed {
+            Some(key) => key,
+
+            //? Going back or confirming without pressing a key means that a
+            //? controller did it. It has no key to give, so it cancels.
+            None if phi.events.now.back == Some(true) ||
+                    phi.events.now.confirm == Some(true) => {
+                self.listening = false;
+                return self.refresh_labels(phi);
+            },
+
+            None => return Ok(()),
+        };
+
+        let action = self.actions[self.selected];
+
+        match key {
+            Keycode::Escape => {},
+            Keycode::Backspace => phi.events.bindings.reset(action),
+            _ => phi.events.bindings.toggle(action, key),
+        }
+
+        if let Err(e) = phi.events.bindings.save() {
+            println!("Could not save the controls: {}", e);
+        }
+
+        self.listening = false;
+        self.refresh_labels(phi)
+    }
+}
+
+impl View for ControlsView {
+    fn update(&mut self, phi: &mut Phi, dt: f64) -> ViewAction {
+        self.bg.update(dt);
+
+        if phi.events.now.quit {
+            return ViewAction::Quit;
+        }
+
+        //? While we wait for a key, it must not trigger the action it is
+        //? currently bound to.
+        if self.listening {
+            return match self.listen(phi) {
+                Ok(()) => ViewAction::None,
+                Err(e) => ViewAction::Fail(e),
+            };
+        }
+
+        if phi.events.now.back == Some(true) {
+            if self.nested {
+                return ViewAction::Pop;
+            }
+
+            let bg = self.bg.clone();
+            return ViewAction::change(::views::main_menu::MainMenuView::with_backgrounds(phi, bg));
+        }
+
+        if phi.events.now.confirm == Some(true) {
+            self.listening = true;
+            if let Err(e) = self.refresh_labels(phi) {
+                return ViewAction::Fail(e);
+            }
+        }
+
+        // Change the selected action, going 'round at either end.
+        if phi.events.now.move_up == Some(true) {
+            self.selected = (self.selected + self.actions.len() - 1) % self.actions.len();
+        }
+
+        if phi.events.now.move_down == Some(true) {
+            self.selected = (self.selected + 1) % self.actions.len();
+        }
+
+        ViewAction::None
+    }
+
+    fn render(&mut self, phi: &mut Phi, alpha: f64) {
+        // Clear the screen
+        phi.renderer.set_draw_color(Color::RGB(0, 0, 0));
+        phi.renderer.clear();
+
+        // Render the backgrounds
+        self.bg.back.render(&mut phi.renderer, alpha);
+        self.bg.middle.render(&mut phi.renderer, alpha);
+        self.bg.front.render(&mut phi.renderer, alpha);
+
+        // Definitions for the layout
+        let (win_w, win_h) = phi.output_size();
+        let label_h = 40.0;
+        let box_w = 500.0;
+        let box_h = self.labels.len() as f64 * label_h;
+        let margin_h = 10.0;
+
+        // Render the colored box which holds the labels
+        phi.renderer.set_draw_color(Color::RGB(140, 30, 140));
+        phi.renderer.fill_rect(Rectangle {
+            w: box_w,
+            h: box_h + margin_h * 2.0,
+            x: (win_w - box_w) / 2.0,
+            y: (win_h - box_h) / 2.0 - margin_h,
+        }.to_sdl().unwrap());
+
+        for (i, &(ref idle, ref hover)) in self.labels.iter().enumerate() {
+            let sprite = if self.selected == i { hover } else { idle };
+            let (w, h) = sprite.size();
+
+            phi.renderer.copy_sprite(sprite, Rectangle {
+                w: w,
+                h: h,
+                x: (win_w - w) / 2.0,
+                y: (win_h - box_h + label_h - h) / 2.0 + label_h * i as f64,
+            });
+        }
+    }
+}
@@ -1,532 +1,248 @@
 use phi::{Phi, View, ViewAction};
+use phi::assets::AssetError;
 use sdl2::pixels::Color;
 use ::sdl2_mixer::{Chunk, Music};
-use phi::data::{Rectangle, MaybeAlive};
-use std::path::Path;
-use phi::gfx::{CopySprite, Sprite};
-use sdl2::render::{Texture, TextureQuery};
-use sdl2_image::LoadTexture;
-use sdl2::render::Renderer;
-use views::shared::{Background, BgSet};
-use phi::gfx::{AnimatedSprite, AnimatedSpriteDescr};
-use ::views::bullets::*;
-
-/// Pixels traveled by the player's ship every second, when it is moving.
-const PLAYER_SPEED: f64 = 180.0;
-const PLAYER_PATH: &'static str = "assets/spaceship.png";
-const PLAYER_W: f64 = 43.0;
-const PLAYER_H: f64 = 39.0;
-
-const ASTEROID_PATH: &'static str = "assets/asteroid.png";
-const ASTEROIDS_WIDE: usize = 21;
-const ASTEROIDS_HIGH: usize = 7;
-const ASTEROIDS_TOTAL: usize = ASTEROIDS_WIDE * ASTEROIDS_HIGH - 4;
-const ASTEROID_SIDE: f64 = 96.0;
-
-const EXPLOSION_PATH: &'static str = "assets/explosion.png";
-const EXPLOSIONS_WIDE: usize = 5;
-const EXPLOSIONS_HIGH: usize = 4;
-const EXPLOSIONS_TOTAL: usize = 17;
-const EXPLOSION_SIDE: f64 = 96.0;
-const EXPLOSION_FPS: f64 = 16.0;
-const EXPLOSION_DURATION: f64 = 1.0 / EXPLOSION_FPS * EXPLOSIONS_TOTAL as f64;
+use std::rc::Rc;
+use phi::gfx::{CopySprite, Sprite, Transform};
+use views::hud::Hud;
+use views::powerups::PowerUp;
+use views::scores::{self, HighScores};
+use views::shared::BgSet;
+use phi::gfx::{self, Animation};
+use views::world::*;
+
+pub const MUSIC_PATH: &'static str = "mdk_phoenix_orchestral.ogg";
+pub const BULLET_SOUND_PATH: &'static str = "bullet.ogg";
+pub const EXPLOSION_SOUND_PATH: &'static str = "explosion.ogg";
+
+pub const PLAYER_SHEET: &'static str = "spaceship.sheet";
+pub const ASTEROID_SHEET: &'static str = "asteroid.sheet";
+pub const EXPLOSION_SHEET: &'static str = "explosion.sheet";
+
+/// The animations of the ship's spritesheet showing it in each of the
+/// directions in which it may be moving, in the order of `PlayerFrame`.
+const PLAYER_FRAMES: [&'static str; 9] = [
+    "up_norm", "up_fast", "up_slow",
+    "mid_norm", "mid_fast", "mid_slow",
+    "down_norm", "down_fast", "down_slow",
+];
+
+/// How long the game goes on once the player's last ship has been destroyed,
+/// before the results are shown.
+const GAME_OVER_DELAY: f64 = 2.0;
+
+/// How many times per second a ship which just arrived blinks, while it is
+/// invulnerable.
+const BLINK_RATE: f64 = 8.0;
 
 const DEBUG: bool = false;
 
-/// The different states our ship might be in. In the image, they're ordered
-/// from left to right, then from top to bottom.
-#[derive(Clone, Copy)]
-enum PlayerFrame {
-    UpNorm   = 0,
-    UpFast   = 1,
-    UpSlow   = 2,
-    MidNorm  = 3,
-    MidFast  = 4,
-    MidSlow  = 5,
-    DownNorm = 6,
-    DownFast = 7,
-    DownSlow = 8
-}
-
-struct Player {
-    rect: Rectangle,
-    sprites: Vec<Sprite>,
-    current: PlayerFrame,
-}
-
-impl Player {
-    pub fn new(phi: &mut Phi) -> Player {
-        // Get the spaceship's sprites
-        let spritesheet = Sprite::load(&mut phi.renderer, PLAYER_PATH).unwrap();
-        let mut sprites = Vec::with_capacity(9);
-
-        for y in 0..3 {
-            for x in 0..3 {
-                sprites.push(spritesheet.region(Rectangle {
-                    w: PLAYER_W,
-                    h: PLAYER_H,
-                    x: PLAYER_W * x as f64,
-                    y: PLAYER_H * y as f64,
-                }).unwrap());
-            }
-        }
-
-        Player {
-            // Spawn the player at the center of the screen, vertically.
-            rect: Rectangle {
-                x: 64.0,
-                y: (phi.output_size().1 - PLAYER_H) / 2.0,
-                w: PLAYER_W,
-                h: PLAYER_H,
-            },
-            sprites: sprites,
-            current: PlayerFrame::MidNorm
-        }
-    }
-
-    pub fn update(&mut self, phi: &mut Phi, elapsed: f64) {
-        // Change the player's cannons
-
-        // Move the player's ship
-
-        let diagonal =
-        (phi.events.key_up ^ phi.events.key_down) &&
-            (phi.events.key_left ^ phi.events.key_right);
-
-        let moved =
-        if diagonal { 1.0 / 2.0f64.sqrt() }
-            else { 1.0 } * PLAYER_SPEED * elapsed;
-
-        let dx = match (phi.events.key_left, phi.events.key_right) {
-            (true, true) | (false, false) => 0.0,
-            (true, false) => -moved,
-            (false, true) => moved,
-        };
-
-        let dy = match (phi.events.key_up, phi.events.key_down) {
-            (true, true) | (false, false) => 0.0,
-            (true, false) => -moved,
-            (false, true) => moved,
-        };
-
-        self.rect.x += dx;
-        self.rect.y += dy;
-
-        // The movable region spans the entire height of the window and 70% of its
-        // width. This way, the player cannot get to the far right of the screen, where
-        // we will spawn the asteroids, and get immediately eliminated.
-        //
-        // We restrain the width because most screens are wider than they are high.
-        let movable_region = Rectangle {
-            x: 0.0,
-            y: 0.0,
-            w: phi.output_size().0 as f64 * 0.70,
-            h: phi.output_size().1 as f64,
-        };
-
-        // If the player cannot fit in the screen, then there is a problem and
-        // the game should be promptly aborted.
-        self.rect = self.rect.move_inside(movable_region).unwrap();
-
-        // Select the appropriate sprite of the ship to show.
-        self.current =
-            if dx == 0.0 && dy < 0.0       { PlayerFrame::UpNorm }
-                else if dx > 0.0 && dy < 0.0   { PlayerFrame::UpFast }
-                    else if dx < 0.0 && dy < 0.0   { PlayerFrame::UpSlow }
-                        else if dx == 0.0 && dy == 0.0 { PlayerFrame::MidNorm }
-                            else if dx > 0.0 && dy == 0.0  { PlayerFrame::MidFast }
-                                else if dx < 0.0 && dy == 0.0  { PlayerFrame::MidSlow }
-                                    else if dx == 0.0 && dy > 0.0  { PlayerFrame::DownNorm }
-                                        else if dx > 0.0 && dy > 0.0   { PlayerFrame::DownFast }
-                                            else if dx < 0.0 && dy > 0.0   { PlayerFrame::DownSlow }
-                                                else { unreachable!() };
-    }
-
-    pub fn render(&self, phi: &mut Phi) {
-        // Render the bounding box (for debugging purposes)
-        if DEBUG {
-            phi.renderer.set_draw_color(Color::RGB(200, 200, 50));
-            phi.renderer.fill_rect(self.rect.to_sdl().unwrap());
-        }
-
-        // Render the ship's current sprite.
-        phi.renderer.copy_sprite(
-            &self.sprites[self.current as usize],
-            self.rect);
-    }
-
-    fn spawn_bullets(&self) -> Vec<Box<Bullet>> {
-        let cannons_x = self.rect.x + 30.0;
-        let cannon1_y = self.rect.y + 6.0;
-        let cannon2_y = self.rect.y + PLAYER_H - 10.0;
-        spawn_bullets(cannons_x, cannon1_y, cannon2_y)
-    }
-}
-
+/// Draws a `GameWorld` and feeds it the player's input.
 pub struct GameView {
-    player: Player,
-    bullets: Vec<Box<Bullet>>,
-    asteroids: Vec<Asteroid>,
-    asteroid_factory: AsteroidFactory,
-    explosions: Vec<Explosion>,
-    explosion_factory: ExplosionFactory,
+    world: GameWorld,
+    player_sprites: Vec<Sprite>,
+
+    /// The frames of the entities of the world, in the order of
+    /// `ASTEROID_ART` and `EXPLOSION_ART`.
+    art: Vec<Vec<Sprite>>,
     bg: BgSet,
-    music: Music,
-    bullet_sound: Chunk,
-    explosion_sound: Chunk,
+    music: Rc<Music>,
+    bullet_sound: Rc<Chunk>,
+    explosion_sound: Rc<Chunk>,
+    hud: Hud,
+
+    /// The time left before the results are shown, once the player has lost
+    /// every ship.
+    game_over_in: Option<f64>,
 }
 
 impl GameView {
-    pub fn new(phi: &mut Phi, bg: BgSet) -> GameView {
-        let bg = BgSet::new(&mut phi.renderer);
-        GameView::with_backgrounds(phi, bg)
-    }
-
-    pub fn with_backgrounds(phi: &mut Phi, bg: BgSet) -> GameView {
-        let music = Music::from_file(Path::new("assets/mdk_phoenix_orchestral.ogg"))
-            .unwrap();
-
-        music.play(-1).unwrap();
-
-        let bullet_sound =
-        Chunk::from_file(Path::new("assets/bullet.ogg"))
-            .unwrap();
-
-        let explosion_sound =
-        Chunk::from_file(Path::new("assets/explosion.ogg"))
-            .unwrap();
-
-        let spritesheet = Sprite::load(&mut phi.renderer, "assets/spaceship.png").unwrap();
-        let mut sprites = Vec::with_capacity(9);
-
-        for y in 0..3 {
-            for x in 0..3 {
-                sprites.push(spritesheet.region(Rectangle {
-                    w: PLAYER_W,
-                    h: PLAYER_H,
-                    x: PLAYER_W * x as f64,
-                    y: PLAYER_H * y as f64,
-                }).unwrap());
-            }
-        }
+    pub fn with_backgrounds(phi: &mut Phi, bg: BgSet) -> Result<GameView, AssetError> {
+        let music = phi.assets.music(MUSIC_PATH)?;
+
+        music.play(-1)
+            .map_err(|e| AssetError::Corrupt(phi.assets.path(MUSIC_PATH), e))?;
+
+        let bullet_sound = phi.assets.chunk(BULLET_SOUND_PATH)?;
+        let explosion_sound = phi.assets.chunk(EXPLOSION_SOUND_PATH)?;
+
+        // Read the spaceship's, asteroid's and explosion's images from the
+        // filesystem and split them into frames, as told by their sheets.
+        let player_sheet = phi.assets.sheet(PLAYER_SHEET)?;
+        let player_frames = gfx::load_frames(phi, player_sheet.descr())?;
+        let player_frame_masks = phi.assets.masks(&player_sheet.descr())?;
+        let mut player_sprites = Vec::with_capacity(PLAYER_FRAMES.len());
+        let mut player_masks = Vec::with_capacity(PLAYER_FRAMES.len());
+
+        for name in &PLAYER_FRAMES {
+            //? Every direction is still, so only its first frame is shown.
+            let range = player_sheet.range(name)
+                .map_err(|e| AssetError::Corrupt(phi.assets.path(PLAYER_SHEET), e))?;
+            player_sprites.push(player_frames[range.start].clone());
+            player_masks.push(player_frame_masks[range.start].clone());
+        }
+
+        let (asteroid_sprites, asteroid_anim) = load_animation(phi, ASTEROID_SHEET, "spin")?;
+        let (explosion_sprites, explosion_anim) = load_animation(phi, EXPLOSION_SHEET, "explode")?;
+
+        // Which pixels of the frames are solid, for the entities which
+        // collide with them.
+        let asteroid_sheet = phi.assets.sheet(ASTEROID_SHEET)?;
+        let asteroid_range = asteroid_sheet.range("spin")
+            .map_err(|e| AssetError::Corrupt(phi.assets.path(ASTEROID_SHEET), e))?;
+        let asteroid_masks = phi.assets.masks(&asteroid_sheet.descr())?[asteroid_range].to_vec();
+
+        let art = WorldArt {
+            asteroid: asteroid_anim,
+            explosion: explosion_anim,
+            player_masks: player_masks,
+            asteroid_masks: asteroid_masks,
+        };
 
-        GameView {
-            player: Player {
-                rect: Rectangle {
-                    x: 64.0,
-                    y: 64.0,
-                    w: PLAYER_W,
-                    h: PLAYER_H,
-                },
-                sprites: sprites,
-                current: PlayerFrame::MidNorm
-            },
-            //? We start with no bullets. Because the size of the vector will
-            //? change drastically throughout the program, there is not much
-            //? point in giving it a capacity.
-            bullets: vec![],
-            asteroids: vec![],
-            asteroid_factory: Asteroid::factory(phi),
-            explosions: vec![],
-            explosion_factory: Explosion::factory(phi),
+        Ok(GameView {
+            world: GameWorld::new(phi.output_size(), phi.next_seed(), phi.lives, art),
+            player_sprites: player_sprites,
+            art: vec![asteroid_sprites, explosion_sprites],
             bg: bg,
             music: music,
             bullet_sound: bullet_sound,
             explosion_sound: explosion_sound,
-        }
+            hud: Hud::new(),
+            game_over_in: None,
+        })
     }
 }
 
+/// The frames of the animation called `name` in the spritesheet at
+/// `sheet_path`, and how they should be played.
+fn load_animation(phi: &mut Phi, sheet_path: &str, name: &str) -> Result<(Vec<Sprite>, Animation), AssetError> {
+    let sheet = phi.assets.sheet(sheet_path)?;
+    let frames = gfx::load_frames(phi, sheet.descr())?;
+    let corrupt = |e| AssetError::Corrupt(phi.assets.path(sheet_path), e);
+
+    let range = sheet.range(name).map_err(&corrupt)?;
+    let anim = sheet.animation(name).map_err(&corrupt)?;
+
+    Ok((frames[range].to_vec(), anim))
+}
 
 impl View for GameView {
-    fn render(&mut self, phi: &mut Phi, elapsed: f64) -> ViewAction {
-        if phi.events.now.quit || phi.events.now.key_escape == Some(true) {
+    fn update(&mut self, phi: &mut Phi, dt: f64) -> ViewAction {
+        if phi.events.now.quit {
             return ViewAction::Quit;
         }
 
-        // Update the player
-        self.player.update(phi, elapsed);
-
-        // Update the bullets
-        self.bullets =
-            ::std::mem::replace(&mut self.bullets, vec![])
-                .into_iter()
-                .filter_map(|bullet| bullet.update(phi, elapsed))
-                .collect();
-
-        // Update the asteroid
-        self.asteroids =
-            ::std::mem::replace(&mut self.asteroids, vec![])
-                .into_iter()
-                .filter_map(|asteroid| asteroid.update(elapsed))
-                .collect();
-
-        // Update the explosions
-        self.explosions =
-            ::std::mem::replace(&mut self.explosions, vec![])
-                .into_iter()
-                .filter_map(|explosion| explosion.update(elapsed))
-                .collect();
-
-        // Collision detection 碰撞检测
-        let mut player_alive = true;
-
-        let mut transition_bullets: Vec<_> =
-        ::std::mem::replace(&mut self.bullets, vec![])
-            .into_iter()
-            .map(|bullet| MaybeAlive { alive: true, value: bullet })
-            .collect();
-
-        self.asteroids =
-            ::std::mem::replace(&mut self.asteroids, vec![])
-                .into_iter()
-                .filter_map(|asteroid| {
-                    // By default, the asteroid has not been in a collision.
-                    let mut asteroid_alive = true;
-
-                    for bullet in &mut transition_bullets {
-                        if asteroid.rect().overlaps(bullet.value.rect()) {
-                            asteroid_alive = false;
-                            bullet.alive = false;
-                        }
-                    }
-
-                    // The player's ship is destroyed if it is hit by an asteroid.
-                    // In which case, the asteroid is also destroyed.
-                    if asteroid.rect().overlaps(self.player.rect) {
-                        asteroid_alive = false;
-                        player_alive = false;
-                    }
-
-                    if asteroid_alive {
-                        Some(asteroid)
-                    } else {
-                        // Spawn an explosive wherever an asteroid was destroyed.
-                        self.explosions.push(
-                            self.explosion_factory.at_center(
-                                asteroid.rect().center()));
-
-                        phi.play_sound(&self.explosion_sound);
-
-                        None
-                    }
-                })
-                .collect();
-
-        self.bullets = transition_bullets.into_iter()
-            .filter_map(MaybeAlive::as_option)
-            .collect();
-
-        // TODO
-        // For the moment, we won't do anything about the player dying. This will be
-        // the subject of a future episode.
-        if !player_alive {
-            println!("The player's ship has been destroyed.");
+        //? Nothing in the game is updated while the pause menu is open, so
+        //? it resumes exactly where it was left.
+        if phi.events.now.pause == Some(true) {
+            let bg = self.bg.clone();
+            return ViewAction::push(::views::pause::PauseView::new(phi, bg));
         }
 
-        // Allow the player to shoot after the bullets are updated, so that,
-        // when rendered for the first time, they are drawn wherever they
-        // spawned.
-        //
-        //? In this case, we ensure that the new bullets are drawn at the tips
-        //? of the cannons.
-        //?
-        //? The `Vec::append` method moves the content of `spawn_bullets` at
-        //? the end of `self.bullets`. After this is done, the vector returned
-        //? by `spawn_bullets` will be empty.
-        if phi.events.now.key_space == Some(true) {
-            self.bullets.append(&mut self.player.spawn_bullets());
-            phi.play_sound(&self.bullet_sound);
-        }
+        // Advance the game, then react to what happened in it.
+        let input = WorldInput::from_events(&phi.events);
 
-        // Randomly create an asteroid about once every 100 frames, that is,
-        // a bit more often than once every two seconds.
-        if ::rand::random::<usize>() % 100 == 0 {
-            self.asteroids.push(self.asteroid_factory.random(phi));
-        }
+        for event in self.world.step(&input, dt) {
+            match event {
+                WorldEvent::ShotFired =>
+                    phi.play_sound(&self.bullet_sound),
 
-        // Clear the screen
-        phi.renderer.set_draw_color(Color::RGB(0, 0, 0));
-        phi.renderer.clear();
+                WorldEvent::AsteroidDestroyed =>
+                    phi.play_sound(&self.explosion_sound),
 
-        // Render the scene 去掉ship的背景
-        //phi.renderer.set_draw_color(Color::RGB(200, 200, 50));
-        //phi.renderer.fill_rect(self.player.rect.to_sdl().unwrap());
+                WorldEvent::PlayerDestroyed =>
+                    phi.play_sound(&self.explosion_sound),
 
-        // Render the Backgrounds
-        self.bg.back.render(&mut phi.renderer, elapsed);
-        self.bg.middle.render(&mut phi.renderer, elapsed);
+                //? The HUD shows what the power-up did, which is enough for
+                //? now.
+                WorldEvent::PowerUpCollected(_) => {},
 
-        // Render the bounding box (for debugging purposes)
-        if DEBUG {
-            phi.renderer.set_draw_color(Color::RGB(200, 200, 50));
-            phi.renderer.fill_rect(self.player.rect.to_sdl().unwrap());
+                WorldEvent::GameOver =>
+                    self.game_over_in = Some(GAME_OVER_DELAY),
+            }
         }
 
-        // Render the ship
-        phi.renderer.copy_sprite(
-            &self.player.sprites[self.player.current as usize],
-            self.player.rect
-        );
+        self.bg.update(dt);
+        self.hud.update(phi, self.world.stats.score(), self.world.lives, self.world.wave(),
+                        self.world.player.weapon, &self.world.player.effects);
 
-        // Render the bullets
-        for bullet in &self.bullets {
-            bullet.render(phi);
-        }
+        // Let the ship's explosion play out, then show the results. Players
+        // who made it into the high scores are first asked for their name.
+        if let Some(delay) = self.game_over_in {
+            if delay <= 0.0 {
+                let bg = self.bg.clone();
+                let stats = self.world.stats;
+                let mode = scores::mode_name(phi.lives);
 
-        // Render the asteroid
-        for asteroid in &self.asteroids {
-            asteroid.render(phi);
-        }
+                //? The music is kept by `phi.assets`, so it does not stop by
+                //? itself when the view is dropped.
+                Music::halt();
 
-        for explosion in &self.explosions {
-            explosion.render(phi);
-        }
+                return
+                    if HighScores::open(phi).qualifies(&mode, stats.score()) {
+                        ViewAction::change(::views::high_scores::NameEntryView::new(phi, bg, stats))
+                    } else {
+                        ViewAction::change(::views::game_over::GameOverView::new(phi, bg, stats, None))
+                    };
+            }
 
-        // Render the foreground
-        self.bg.front.render(&mut phi.renderer, elapsed);
+            self.game_over_in = Some(delay - dt);
+        }
 
         ViewAction::None
     }
-}
-
-//小行星
-struct Asteroid {
-    sprite: AnimatedSprite,
-    rect: Rectangle,
-    vel: f64,//速率
-}
 
-impl Asteroid {
-    fn factory(phi: &mut Phi) -> AsteroidFactory {
-        AsteroidFactory {
-            sprite: AnimatedSprite::with_fps(
-                AnimatedSprite::load_frames(phi, AnimatedSpriteDescr {
-                    image_path: ASTEROID_PATH,
-                    total_frames: ASTEROIDS_TOTAL,
-                    frames_high: ASTEROIDS_HIGH,
-                    frames_wide: ASTEROIDS_WIDE,
-                    frame_w: ASTEROID_SIDE,
-                    frame_h: ASTEROID_SIDE,
-                }), 1.0),
-        }
-    }
+    fn render(&mut self, phi: &mut Phi, alpha: f64) {
+        // Clear the screen
+        phi.renderer.set_draw_color(Color::RGB(0, 0, 0));
+        phi.renderer.clear();
 
-    fn update(mut self, dt: f64) -> Option<Asteroid> {
-        self.rect.x -= dt * self.vel;
-        self.sprite.add_time(dt);
+        // Render the Backgrounds
+        self.bg.back.render(&mut phi.renderer, alpha);
+        self.bg.middle.render(&mut phi.renderer, alpha);
 
-        if self.rect.x <= -ASTEROID_SIDE {
-            None
-        } else {
-            Some(self)
-        }
-    }
+        let player_rect = self.world.player.rect_at(alpha);
 
-    fn render(&self, phi: &mut Phi) {
+        // Render the bounding box (for debugging purposes)
         if DEBUG {
-            // Render the bounding box
             phi.renderer.set_draw_color(Color::RGB(200, 200, 50));
-            phi.renderer.fill_rect(self.rect().to_sdl().unwrap());
-        }
-        phi.renderer.copy_sprite(&self.sprite, self.rect);
-    }
-
-    fn rect(&self) -> Rectangle {
-        self.rect
-    }
-}
-
-struct AsteroidFactory {
-    sprite: AnimatedSprite,
-}
-
-impl AsteroidFactory {
-    fn random(&self, phi: &mut Phi) -> Asteroid {
-        let (w, h) = phi.output_size();
-
-        // FPS in [10.0, 30.0)
-        let mut sprite = self.sprite.clone();
-        sprite.set_fps(::rand::random::<f64>().abs() * 20.0 + 10.0);
-
-        Asteroid {
-            sprite: sprite,
-
-            // In the screen vertically, and over the right of the screen
-            // horizontally.
-            rect: Rectangle {
-                w: ASTEROID_SIDE,
-                h: ASTEROID_SIDE,
-                x: w,
-                y: ::rand::random::<f64>().abs() * (h - ASTEROID_SIDE),
-            },
-
-            // vel in [50.0, 150.0)
-            vel: ::rand::random::<f64>().abs() * 100.0 + 50.0,
+            phi.renderer.fill_rect(player_rect.to_sdl().unwrap());
         }
-    }
-}
 
-struct Explosion {
-    sprite: AnimatedSprite,
-    rect: Rectangle,
+        // Render the ship, which flashes while it is invulnerable and is
+        // tinted while it is shielded
+        let player = &self.world.player;
+        let blinking = player.invulnerable > 0.0 &&
+            (player.invulnerable * BLINK_RATE * 2.0) as u32 % 2 == 1;
+        let shielded = player.effects.is_active(PowerUp::Shield);
 
-    //? Keep how long its been arrived, so that we destroy the explosion once
-    //? its animation is finished.
-    alive_since: f64,
-}
+        if player.alive {
+            let transform =
+                if blinking { Transform::default().faded(80).tinted(Color::RGB(120, 200, 255)) }
+                else if shielded { Transform::default().tinted(Color::RGB(120, 200, 255)) }
+                else { Transform::default() };
 
-impl Explosion {
-    fn factory(phi: &mut Phi) -> ExplosionFactory {
-        ExplosionFactory {
-            sprite: AnimatedSprite::with_fps(
-                AnimatedSprite::load_frames(phi, AnimatedSpriteDescr {
-                    image_path: EXPLOSION_PATH,
-                    total_frames: EXPLOSIONS_TOTAL,
-                    frames_high: EXPLOSIONS_HIGH,
-                    frames_wide: EXPLOSIONS_WIDE,
-                    frame_w: EXPLOSION_SIDE,
-                    frame_h: EXPLOSION_SIDE,
-                }), EXPLOSION_FPS),
+            phi.renderer.copy_sprite_ex(
+                &self.player_sprites[player.current as usize],
+                player_rect,
+                transform
+            );
         }
-    }
-
-    fn update(mut self, dt: f64) -> Option<Explosion> {
-        self.alive_since += dt;
-        self.sprite.add_time(dt);
 
-        if self.alive_since >= EXPLOSION_DURATION {
-            None
-        } else {
-            Some(self)
+        // Render the bullets, asteroids and explosions
+        if DEBUG {
+            phi.renderer.set_draw_color(Color::RGB(200, 200, 50));
+            for (_, body) in self.world.scene.bodies.iter() {
+                phi.renderer.fill_rect(body.rect_at(alpha).to_sdl().unwrap());
+            }
         }
-    }
 
-    fn render(&self, phi: &mut Phi) {
-        phi.renderer.copy_sprite(&self.sprite, self.rect);
-    }
-}
-
-struct ExplosionFactory {
-    sprite: AnimatedSprite,
-}
+        self.world.scene.render(&mut phi.renderer, alpha, &self.art);
 
-impl ExplosionFactory {
-    fn at_center(&self, center: (f64, f64)) -> Explosion {
-        // FPS in [10.0, 30.0)
-        let mut sprite = self.sprite.clone();
-
-        Explosion {
-            sprite: sprite,
-
-            // In the screen vertically, and over the right of the screen
-            // horizontally.
-            rect: Rectangle::with_size(EXPLOSION_SIDE, EXPLOSION_SIDE)
-                .center_at(center),
+        // Render the foreground
+        self.bg.front.render(&mut phi.renderer, alpha);
 
-            alive_since: 0.0,
-        }
+        // Render the HUD over everything else
+        self.hud.render(phi);
     }
 }
-
-
@@ -0,0 +1,108 @@
+use phi::{Phi, View, ViewAction};
+use phi::assets::AssetError;
+use phi::data::Rectangle;
+use phi::gfx::{CopySprite, Sprite};
+use sdl2::pixels::Color;
+use views::shared::{BgSet, Menu, MENU_FONT};
+use views::world::RunStats;
+
+/// The space between the lines of the results, in pixels.
+const LINE_SPACING: f64 = 6.0;
+
+/// Shown once the player's ship has been destroyed, with how the game went.
+/// The player may then try again, or return to the main menu.
+pub struct GameOverView {
+    /// The title, followed by the results, from top to bottom.
+    lines: Vec<Sprite>,
+    menu: Menu,
+    bg: BgSet,
+}
+
+impl GameOverView {
+    /// `rank` is where the score made it into the high scores, starting at
+    /// 0, if it did.
+    pub fn new(phi: &mut Phi, bg: BgSet, stats: RunStats, rank: Option<usize>) -> Result<GameOverView, AssetError> {
+        let mut lines = vec![
+            phi.ttf_str_sprite("Game Over", MENU_FONT, 48, Color::RGB(255, 255, 255))?,
+        ];
+
+        if let Some(rank) = rank {
+            let text = format!("New record! Rank {}", rank + 1);
+            lines.push(phi.ttf_str_sprite(&text, MENU_FONT, 28, Color::RGB(255, 220, 80))?);
+        }
+
+        for text in &[
+            format!("Score: {}", stats.score()),
+            format!("Survived: {:.1} s", stats.time),
+            format!("Asteroids destroyed: {}", stats.asteroids_destroyed),
+            format!("Shots fired: {}", stats.shots_fired),
+        ] {
+            lines.push(phi.ttf_str_sprite(text, MENU_FONT, 24, Color::RGB(220, 220, 220))?);
+        }
+
+        Ok(GameOverView {
+            lines: lines,
+            menu: Menu::new(phi, &["Retry", "Main Menu"])?,
+            bg: bg,
+        })
+    }
+}
+
+impl View for GameOverView {
+    fn update(&mut self, phi: &mut Phi, dt: f64) -> ViewAction {
+        if phi.events.now.quit {
+            return ViewAction::Quit;
+        }
+
+        self.bg.update(dt);
+
+        let bg = self.bg.clone();
+
+        if phi.events.now.back == Some(true) {
+            return ViewAction::change(::views::main_menu::MainMenuView::with_backgrounds(phi, bg));
+        }
+
+        match self.menu.update(phi) {
+            Some(0) => ViewAction::change(::views::game::GameView::with_backgrounds(phi, bg)),
+            Some(1) => ViewAction::change(::views::main_menu::MainMenuView::with_backgrounds(phi, bg)),
+            _ => ViewAction::None,
+        }
+    }
+
+    fn render(&mut self, phi: &mut Phi, alpha: f64) {
+        // Clear the screen
+        phi.renderer.set_draw_color(Color::RGB(0, 0, 0));
+        phi.renderer.clear();
+
+        // Render the backgrounds
+        self.bg.back.render(&mut phi.renderer, alpha);
+        self.bg.middle.render(&mut phi.renderer, alpha);
+        self.bg.front.render(&mut phi.renderer, alpha);
+
+        // Render the title and the results right above the menu, centered
+        // horizontally.
+        //? If the window is too short for them to fit between its top and
+        //? the menu, they are shrunk rather than drawn over the menu.
+        let (win_w, win_h) = phi.output_size();
+        let bottom = self.menu.top(phi) - LINE_SPACING * 2.0;
+        let height = self.lines.iter()
+            .map(|line| line.size().1 + LINE_SPACING)
+            .sum::<f64>() - LINE_SPACING;
+        let scale = ((bottom - win_h * 0.02) / height).min(1.0).max(0.0);
+        let mut y = bottom - height * scale;
+
+        for line in &self.lines {
+            let (w, h) = line.size();
+            let (w, h) = (w * scale, h * scale);
+            phi.renderer.copy_sprite(line, Rectangle {
+                w: w,
+                h: h,
+                x: (win_w - w) / 2.0,
+                y: y,
+            });
+            y += h + LINE_SPACING * scale;
+        }
+
+        self.menu.render(phi);
+    }
+}
@@ -0,0 +1,216 @@
+use phi::{Phi, View, ViewAction};
+use phi::assets::AssetError;
+use phi::data::Rectangle;
+use phi::gfx::{CopySprite, Sprite};
+use sdl2::keyboard::Keycode;
+use sdl2::pixels::Color;
+use views::scores::{self, HighScores, MAX_NAME_LEN};
+use views::shared::{BgSet, MENU_FONT};
+use views::world::RunStats;
+
+/// Renders `lines` from the top of the window, centered horizontally.
+fn render_lines(phi: &mut Phi, lines: &[Sprite]) {
+    let (win_w, win_h) = phi.output_size();
+    let mut y = win_h * 0.08;
+
+    for line in lines {
+        let (w, h) = line.size();
+        phi.renderer.copy_sprite(line, Rectangle {
+            w: w,
+            h: h,
+            x: (win_w - w) / 2.0,
+            y: y,
+        });
+        y += h + 6.0;
+    }
+}
+
+/// Lists the best scores of the current game mode.
+pub struct HighScoresView {
+    /// The title, followed by the scores, from top to bottom.
+    lines: Vec<Sprite>,
+    bg: BgSet,
+}
+
+impl HighScoresView {
+    pub fn new(phi: &mut Phi, bg: BgSet) -> Result<HighScoresView, AssetError> {
+        let scores = HighScores::open(phi);
+        let entries = scores.entries(&scores::mode_name(phi.lives));
+
+        let mut texts = vec![];
+        if entries.is_empty() {
+            texts.push("No scores yet".to_string());
+        }
+        for (i, entry) in entries.iter().enumerate() {
+            texts.push(format!("{}. {}  {}", i + 1, entry.name, entry.score));
+        }
+
+        let title = format!("High Scores ({} lives)", phi.lives);
+        let mut lines = vec![
+            phi.ttf_str_sprite(&title, MENU_FONT, 48, Color::RGB(255, 255, 255))?,
+        ];
+
+        for text in &texts {
+            lines.push(phi.ttf_str_sprite(text, MENU_FONT, 28, Color::RGB(220, 220, 220))?);
+        }
+
+        Ok(HighScoresView {
+            lines: lines,
+            bg: bg,
+        })
+    }
+}
+
+impl View for HighScoresView {
+    fn update(&mut self, phi: &mut Phi, dt: f64) -> ViewAction {
+        if phi.events.now.quit {
+            return ViewAction::Quit;
+        }
+
+        self.bg.update(dt);
+
+        if phi.events.now.back == Some(true) || phi.events.now.confirm == Some(true) ||
+            phi.events.now.mouse_left == Some(true) {
+            let bg = self.bg.clone();
+            return ViewAction::change(::views::main_menu::MainMenuView::with_backgrounds(phi, bg));
+        }
+
+        ViewAction::None
+    }
+
+    fn render(&mut self, phi: &mut Phi, alpha: f64) {
+        // Clear the screen
+        phi.renderer.set_draw_color(Color::RGB(0, 0, 0));
+        phi.renderer.clear();
+
+        // Render the backgrounds
+        self.bg.back.render(&mut phi.renderer, alpha);
+        self.bg.middle.render(&mut phi.renderer, alpha);
+        self.bg.front.render(&mut phi.renderer, alpha);
+
+        render_lines(phi, &self.lines);
+    }
+}
+
+/// Asks for the name of a player whose score made it into the high scores,
+/// before showing the results.
+///
+/// Letters and digits are typed in, `Backspace` erases the last one, `Return`
+/// saves the score and `Escape` leaves without saving it.
+pub struct NameEntryView {
+    stats: RunStats,
+    name: String,
+
+    /// The title and the prompt, followed by the name typed so far.
+    lines: Vec<Sprite>,
+
+    bg: BgSet,
+}
+
+impl NameEntryView {
+    pub fn new(phi: &mut Phi, bg: BgSet, stats: RunStats) -> Result<NameEntryView, AssetError> {
+        let mut view = NameEntryView {
+            stats: stats,
+            name: String::new(),
+            lines: vec![],
+            bg: bg,
+        };
+
+        view.refresh_lines(phi)?;
+        Ok(view)
+    }
+
+    /// Renders the lines again, e.g. because the name changed.
+    fn refresh_lines(&mut self, phi: &mut Phi) -> Result<(), AssetError> {
+        let score = format!("New record: {}", self.stats.score());
+        let name = format!("{}_", self.name);
+
+        self.lines = vec![
+            phi.ttf_str_sprite(&score, MENU_FONT, 48, Color::RGB(255, 255, 255))?,
+            phi.ttf_str_sprite("Enter your name", MENU_FONT, 28, Color::RGB(220, 220, 220))?,
+            phi.ttf_str_sprite(&name, MENU_FONT, 42, Color::RGB(255, 255, 255))?,
+        ];
+
+        Ok(())
+    }
+
+    /// Saves the score under the name typed so far, then shows the results.
+    fn finish(&mut self, phi: &mut Phi) -> ViewAction {
+        let name = if self.name.is_empty() { "???".to_string() } else { self.name.clone() };
+
+        let mut scores = HighScores::open(phi);
+        let rank = scores.insert(&scores::mode_name(phi.lives), name, self.stats.score());
+
+        //? A replay has nowhere to save its scores, see `HighScores::open`.
+        if !phi.replaying {
+            if let Err(e) = scores.save() {
+                println!("Could not save the high scores: {}", e);
+            }
+        }
+
+        self.show_results(phi, rank)
+    }
+
+    fn show_results(&mut self, phi: &mut Phi, rank: Option<usize>) -> ViewAction {
+        let bg = self.bg.clone();
+        ViewAction::change(::views::game_over::GameOverView::new(phi, bg, self.stats, rank))
+    }
+}
+
+impl View for NameEntryView {
+    fn update(&mut self, phi: &mut Phi, dt: f64) -> ViewAction {
+        if phi.events.now.quit {
+            return ViewAction::Quit;
+        }
+
+        self.bg.update(dt);
+
+        match phi.events.now.key_pressed {
+            Some(Keycode::Return) => return self.finish(phi),
+            Some(Keycode::Escape) => return self.show_results(phi, None),
+
+            Some(Keycode::Backspace) => {
+                self.name.pop();
+                if let Err(e) = self.refresh_lines(phi) {
+                    return ViewAction::Fail(e);
+                }
+            },
+
+            Some(key) => {
+                //? Only keys named after a single letter or digit are typed
+                //? in, which rules out e.g. `Space` or `Left Shift`.
+                let name = key.name();
+                let mut chars = name.chars();
+
+                if let (Some(c), None) = (chars.next(), chars.next()) {
+                    if c.is_ascii_alphanumeric() && self.name.len() < MAX_NAME_LEN {
+                        self.name.push(c.to_ascii_uppercase());
+                        if let Err(e) = self.refresh_lines(phi) {
+                            return ViewAction::Fail(e);
+                        }
+                    }
+                }
+            },
+
+            // Controllers have no keys, so they save the score as it is.
+            None => if phi.events.now.confirm == Some(true) {
+                return self.finish(phi);
+            },
+        }
+
+        ViewAction::None
+    }
+
+    fn render(&mut self, phi: &mut Phi, alpha: f64) {
+        // Clear the screen
+        phi.renderer.set_draw_color(Color::RGB(0, 0, 0));
+        phi.renderer.clear();
+
+        // Render the backgrounds
+        self.bg.back.render(&mut phi.renderer, alpha);
+        self.bg.middle.render(&mut phi.renderer, alpha);
+        self.bg.front.render(&mut phi.renderer, alpha);
+
+        render_lines(phi, &self.lines);
+    }
+}
@@ -0,0 +1,154 @@
+use phi::Phi;
+use phi::data::Rectangle;
+use phi::gfx::{CopySprite, Sprite};
+use sdl2::pixels::Color;
+use views::bullets::Weapon;
+use views::powerups::{Effects, PowerUp};
+
+pub const HUD_FONT: &'static str = "belligerent.ttf";
+
+/// The space between the counters and the edges of the window.
+const MARGIN: f64 = 10.0;
+
+/// A labelled value, whose sprite is only rendered again when the value
+/// changes.
+struct Counter {
+    label: &'static str,
+    value: Option<String>,
+    sprite: Option<Sprite>,
+}
+
+impl Counter {
+    fn new(label: &'static str) -> Counter {
+        Counter {
+            label: label,
+            value: None,
+            sprite: None,
+        }
+    }
+
+    fn set<T: ToString>(&mut self, phi: &mut Phi, value: T) {
+        let value = value.to_string();
+        if self.value.as_ref() != Some(&value) {
+            let text = format!("{} {}", self.label, value);
+            //? The HUD is not worth stopping the game for, so a counter
+            //? which cannot be rendered is simply not shown.
+            self.sprite = phi.ttf_str_sprite(&text, HUD_FONT, 24, Color::RGB(255, 255, 255)).ok();
+            self.value = Some(value);
+        }
+    }
+}
+
+/// The score, lives, wave and weapon, drawn in the corners of the window over
+/// the game, and the seconds left to the power-ups in effect, under the score.
+pub struct Hud {
+    score: Counter,
+    lives: Counter,
+    wave: Counter,
+    weapon: Counter,
+
+    /// A countdown for every running power-up, in the order in which they
+    /// were picked up.
+    effects: Vec<(PowerUp, Counter)>,
+}
+
+impl Hud {
+    pub fn new() -> Hud {
+        Hud {
+            score: Counter::new("Score"),
+            lives: Counter::new("Lives"),
+            wave: Counter::new("Wave"),
+            weapon: Counter::new("Weapon"),
+            effects: vec![],
+        }
+    }
+
+    /// Shows the given values from now on.
+    pub fn update(&mut self, phi: &mut Phi, score: u32, lives: u32, wave: u32, weapon: Weapon,
+                  effects: &Effects) {
+        self.score.set(phi, score);
+        self.lives.set(phi, lives);
+        self.wave.set(phi, wave);
+        self.weapon.set(phi, weapon.name());
+
+        //? The countdowns are kept from one update to the next, so that their
+        //? sprites are only rendered again once a second.
+        let mut countdowns = ::std::mem::replace(&mut self.effects, vec![]);
+
+        for &(powerup, left) in effects.running() {
+            let mut counter = match countdowns.iter().position(|&(shown, _)| shown == powerup) {
+                Some(i) => countdowns.remove(i).1,
+                None => Counter::new(powerup.name()),
+            };
+
+            counter.set(phi, left.ceil() as u32);
+            self.effects.push((powerup, counter));
+        }
+    }
+
+    pub fn render(&self, phi: &mut Phi) {
+        //? The counters are placed relative to the size of the screen, so
+        //? that they stay in its corners whatever the resolution.
+        let (win_w, win_h) = phi.output_size();
+
+        // Score in the top-left corner
+        if let Some(ref sprite) = self.score.sprite {
+            let (w, h) = sprite.size();
+            phi.renderer.copy_sprite(sprite, Rectangle {
+                w: w,
+                h: h,
+                x: MARGIN,
+                y: MARGIN,
+            });
+        }
+
+        // Power-ups under the score, one per line
+        let mut y = MARGIN + self.score.sprite.as_ref().map(|sprite| sprite.size().1).unwrap_or(0.0);
+
+        for &(_, ref counter) in &self.effects {
+            if let Some(ref sprite) = counter.sprite {
+                let (w, h) = sprite.size();
+                phi.renderer.copy_sprite(sprite, Rectangle {
+                    w: w,
+                    h: h,
+                    x: MARGIN,
+                    y: y,
+                });
+                y += h;
+            }
+        }
+
+        // Wave in the top-right corner
+        if let Some(ref sprite) = self.wave.sprite {
+            let (w, h) = sprite.size();
+            phi.renderer.copy_sprite(sprite, Rectangle {
+                w: w,
+                h: h,
+                x: win_w - w - MARGIN,
+                y: MARGIN,
+            });
+        }
+
+        // Lives in the bottom-left corner
+        if let Some(ref sprite) = self.lives.sprite {
+            let (w, h) = sprite.size();
+            phi.renderer.copy_sprite(sprite, Rectangle {
+                w: w,
+                h: h,
+                x: MARGIN,
+                y: win_h - h - MARGIN,
+            });
+        }
+
+        // Weapon in the bottom-right corner
+        if let Some(ref sprite) = self.weapon.sprite {
+            let (w, h) = sprite.size();
+            phi.renderer.copy_sprite(sprite, Rectangle {
+                w: w,
+                h: h,
+                x: win_w - w - MARGIN,
+                y: win_h - h - MARGIN,
+            });
+        }
+    }
+}
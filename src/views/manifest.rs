@@ -0,0 +1,25 @@
+//! Every asset which the game needs, so that they may all be checked when it
+//! starts rather than whenever a view first asks for one of them.
+
+use phi::assets::Required;
+use views::{controls, game, hud, shared};
+
+pub fn required() -> Vec<Required> {
+    vec![
+        Required::Image(shared::BG_BACK_PATH),
+        Required::Image(shared::BG_MIDDLE_PATH),
+        Required::Image(shared::BG_FRONT_PATH),
+
+        Required::Spritesheet(game::PLAYER_SHEET),
+        Required::Spritesheet(game::ASTEROID_SHEET),
+        Required::Spritesheet(game::EXPLOSION_SHEET),
+
+        Required::Sound(game::BULLET_SOUND_PATH),
+        Required::Sound(game::EXPLOSION_SOUND_PATH),
+        Required::Music(game::MUSIC_PATH),
+
+        Required::Font(shared::MENU_FONT),
+        Required::Font(controls::LABEL_FONT),
+        Required::Font(hud::HUD_FONT),
+    ]
+}
@@ -1,4 +1,13 @@
 pub mod game;
 pub mod main_menu;
 pub mod shared;
-pub mod bullets;
\ No newline at end of file
+pub mod bullets;
+pub mod powerups;
+pub mod world;
+pub mod controls;
+pub mod pause;
+pub mod game_over;
+pub mod hud;
+pub mod scores;
+pub mod high_scores;
+pub mod manifest;
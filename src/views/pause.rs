@@ -0,0 +1,88 @@
+use phi::{Phi, View, ViewAction};
+use phi::assets::AssetError;
+use phi::data::Rectangle;
+use sdl2::pixels::Color;
+use sdl2::render::BlendMode;
+use sdl2_mixer::{Channel, Music};
+use views::shared::{BgSet, Menu};
+
+/// The menu shown over the game while it is paused. The game and its sounds
+/// are frozen until the player resumes it.
+pub struct PauseView {
+    menu: Menu,
+    bg: BgSet,
+}
+
+impl PauseView {
+    pub fn new(phi: &mut Phi, bg: BgSet) -> Result<PauseView, AssetError> {
+        let menu = Menu::new(phi, &["Resume", "Restart", "Controls", "Main Menu"])?;
+
+        Music::pause();
+        Channel::all().pause();
+
+        Ok(PauseView {
+            menu: menu,
+            bg: bg,
+        })
+    }
+
+    fn resume(&self) -> ViewAction {
+        Music::resume();
+        Channel::all().resume();
+        ViewAction::Pop
+    }
+
+    /// Stops whatever the paused game was playing, before leaving it.
+    fn halt(&self) {
+        Music::halt();
+        Channel::all().halt();
+    }
+}
+
+impl View for PauseView {
+    fn update(&mut self, phi: &mut Phi, _: f64) -> ViewAction {
+        if phi.events.now.quit {
+            return ViewAction::Quit;
+        }
+
+        if phi.events.now.pause == Some(true) || phi.events.now.back == Some(true) {
+            return self.resume();
+        }
+
+        let bg = self.bg.clone();
+
+        match self.menu.update(phi) {
+            Some(0) => self.resume(),
+            Some(1) => {
+                self.halt();
+                ViewAction::reset(::views::game::GameView::with_backgrounds(phi, bg))
+            },
+            Some(2) => ViewAction::push(::views::controls::ControlsView::nested(phi, bg)),
+            Some(3) => {
+                self.halt();
+                ViewAction::reset(::views::main_menu::MainMenuView::with_backgrounds(phi, bg))
+            },
+            _ => ViewAction::None,
+        }
+    }
+
+    fn render(&mut self, phi: &mut Phi, _: f64) {
+        // Darken the game underneath
+        let (win_w, win_h) = phi.output_size();
+        phi.renderer.set_blend_mode(BlendMode::Blend);
+        phi.renderer.set_draw_color(Color::RGBA(0, 0, 0, 160));
+        phi.renderer.fill_rect(Rectangle {
+            x: 0.0,
+            y: 0.0,
+            w: win_w,
+            h: win_h,
+        }.to_sdl().unwrap());
+        phi.renderer.set_blend_mode(BlendMode::None);
+
+        self.menu.render(phi);
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+}
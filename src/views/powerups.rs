@@ -0,0 +1,164 @@
+use phi::data::Rectangle;
+use phi::ecs::{Body, Look};
+use rand::{Rng, XorShiftRng};
+use sdl2::pixels::Color;
+use views::bullets::Bullet;
+
+/// The side of the square in which a power-up is drawn.
+pub const POWERUP_SIDE: f64 = 20.0;
+
+/// Pixels traveled leftwards by a power-up every second.
+pub const POWERUP_SPEED: f64 = 60.0;
+
+/// One out of this many asteroids shot down drops a power-up.
+const DROP_CHANCE: u32 = 5;
+
+/// How long the timed effects last, in seconds.
+const UPGRADE_DURATION: f64 = 10.0;
+const SHIELD_DURATION: f64 = 8.0;
+const SPEED_DURATION: f64 = 10.0;
+
+/// How much faster the ship goes while it is boosted.
+pub const SPEED_BOOST: f64 = 1.5;
+
+/// How much faster and longer bullets are while the weapon is upgraded.
+const UPGRADE_SPEEDUP: f64 = 1.5;
+const UPGRADE_GROWTH: f64 = 2.0;
+
+/// Something dropped by an asteroid, which the player picks up by flying
+/// over it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PowerUp {
+    /// For a while, every weapon fires faster and longer bullets.
+    WeaponUpgrade,
+
+    /// For a while, asteroids break on the ship rather than destroying it.
+    Shield,
+
+    /// One more ship.
+    ExtraLife,
+
+    /// For a while, the ship moves faster.
+    Speed,
+}
+
+impl PowerUp {
+    /// The power-up dropped by an asteroid which was just shot down, if any.
+    //? Extra lives are worth the most, so they are also the rarest.
+    pub fn random_drop(rng: &mut XorShiftRng) -> Option<PowerUp> {
+        if rng.gen_range(0, DROP_CHANCE) != 0 {
+            return None;
+        }
+
+        Some(match rng.gen_range(0, 10) {
+            n if n < 3 => PowerUp::WeaponUpgrade,
+            n if n < 6 => PowerUp::Shield,
+            n if n < 9 => PowerUp::Speed,
+            _ => PowerUp::ExtraLife,
+        })
+    }
+
+    /// How long the effect of the power-up lasts, if it does not take effect
+    /// at once.
+    pub fn duration(self) -> Option<f64> {
+        match self {
+            PowerUp::WeaponUpgrade => Some(UPGRADE_DURATION),
+            PowerUp::Shield => Some(SHIELD_DURATION),
+            PowerUp::Speed => Some(SPEED_DURATION),
+            PowerUp::ExtraLife => None,
+        }
+    }
+
+    /// The name shown to the player.
+    pub fn name(self) -> &'static str {
+        match self {
+            PowerUp::WeaponUpgrade => "Upgrade",
+            PowerUp::Shield => "Shield",
+            PowerUp::ExtraLife => "Extra life",
+            PowerUp::Speed => "Speed",
+        }
+    }
+
+    /// How the power-up is drawn while it drifts.
+    pub fn look(self) -> Look {
+        Look::Fill(match self {
+            PowerUp::WeaponUpgrade => Color::RGB(255, 150, 40),
+            PowerUp::Shield => Color::RGB(120, 200, 255),
+            PowerUp::ExtraLife => Color::RGB(230, 60, 60),
+            PowerUp::Speed => Color::RGB(80, 230, 120),
+        })
+    }
+
+    /// Where a power-up dropped at `center` starts.
+    pub fn rect_at(center: (f64, f64)) -> Rectangle {
+        Rectangle::with_size(POWERUP_SIDE, POWERUP_SIDE).center_at(center)
+    }
+}
+
+/// The timed effects of the power-ups which the ship picked up, and the
+/// seconds left to each of them.
+#[derive(Clone, Debug, Default)]
+pub struct Effects {
+    timers: Vec<(PowerUp, f64)>,
+}
+
+impl Effects {
+    /// Starts the effect of `powerup`, or starts it over if it was already
+    /// running.
+    pub fn start(&mut self, powerup: PowerUp) {
+        if let Some(duration) = powerup.duration() {
+            self.timers.retain(|&(running, _)| running != powerup);
+            self.timers.push((powerup, duration));
+        }
+    }
+
+    /// Counts down the effects by `dt` seconds, and stops those which ran
+    /// out.
+    pub fn update(&mut self, dt: f64) {
+        for timer in &mut self.timers {
+            timer.1 -= dt;
+        }
+        self.timers.retain(|&(_, left)| left > 0.0);
+    }
+
+    pub fn is_active(&self, powerup: PowerUp) -> bool {
+        self.timers.iter().any(|&(running, _)| running == powerup)
+    }
+
+    /// Every running effect and the seconds left to it, in the order in
+    /// which they were picked up.
+    pub fn running(&self) -> &[(PowerUp, f64)] {
+        &self.timers
+    }
+}
+
+/// Upgrades a bullet which is about to be fired from `rect`, whatever the
+/// weapon: it is made longer, and faster.
+pub fn upgrade(rect: Rectangle, bullet: Box<Bullet>) -> (Rectangle, Box<Bullet>) {
+    let rect = Rectangle { w: rect.w * UPGRADE_GROWTH, ..rect };
+    (rect, Box::new(Upgraded(bullet)))
+}
+
+/// Wraps the bullets fired while the weapon is upgraded, so that they go
+/// faster than they would otherwise.
+struct Upgraded(Box<Bullet>);
+
+impl Bullet for Upgraded {
+    fn update(&mut self, body: &mut Body, dt: f64) {
+        //? Everything the bullet does happens sooner, e.g. sine-wave bullets
+        //? also go up and down more often.
+        self.0.update(body, dt * UPGRADE_SPEEDUP)
+    }
+
+    fn look(&self) -> Look {
+        self.0.look()
+    }
+
+    fn pierces(&self) -> bool {
+        self.0.pierces()
+    }
+
+    fn angle(&self) -> f64 {
+        self.0.angle()
+    }
+}
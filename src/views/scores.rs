@@ -0,0 +1,285 @@
+//! The best scores achieved by the player, kept between sessions.
+//!
+//! Scores are stored in a text file in the player's data directory. Its first
+//! line names the format and its version. Every other line holds a game mode,
+//! a score and the name of whoever achieved it, separated by tabs. Lines
+//! which cannot be read are reported and skipped, so that a damaged file
+//! never keeps the game from starting.
+//!
+//! ```text
+//! shoot-asteroid-scores 1
+//! 3_lives	12450	ZOE
+//! ```
+
+use phi::{paths, Phi};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+const MAGIC: &'static str = "shoot-asteroid-scores";
+
+/// The version of the format written by `HighScores::save`.
+pub const VERSION: u32 = 1;
+
+/// The name of the scores file inside of the data directory.
+pub const SCORES_FILE: &'static str = "scores.txt";
+
+/// The number of scores kept for every game mode.
+pub const MAX_ENTRIES: usize = 10;
+
+/// The longest name which may be entered.
+pub const MAX_NAME_LEN: usize = 8;
+
+/// The name of the game mode played with `lives` ships. Scores are only
+/// compared to those of the same mode.
+pub fn mode_name(lives: u32) -> String {
+    format!("{}_lives", lives)
+}
+
+#[derive(Clone, Debug)]
+pub struct Entry {
+    pub name: String,
+    pub score: u32,
+}
+
+pub struct HighScores {
+    /// The best entries of every game mode, from best to worst.
+    tables: HashMap<String, Vec<Entry>>,
+
+    /// Where the scores are saved, if anywhere.
+    path: Option<PathBuf>,
+}
+
+impl HighScores {
+    /// Reads the scores from the player's data directory. If it is unknown,
+    /// or if the game is replaying, then the table starts empty and cannot be
+    /// saved.
+    //? A replay must take the same path wherever it is watched, whatever the
+    //? scores of whoever watches it, and must leave those scores alone.
+    pub fn open(phi: &Phi) -> HighScores {
+        if phi.replaying {
+            return HighScores { tables: HashMap::new(), path: None };
+        }
+
+        match paths::data_dir() {
+            Some(dir) => HighScores::load(&dir.join(SCORES_FILE)),
+            None => HighScores { tables: HashMap::new(), path: None },
+        }
+    }
+
+    /// Reads the scores stored at `path`, which is also where they will be
+    /// saved. If the file does not exist yet, or cannot be understood, then
+    /// the table starts empty.
+    pub fn load(path: &Path) -> HighScores {
+        let mut scores = HighScores {
+            tables: HashMap::new(),
+            path: Some(path.to_path_buf()),
+        };
+
+        let mut contents = String::new();
+        if let Err(e) = File::open(path).and_then(|mut f| f.read_to_string(&mut contents)) {
+            if e.kind() != ::std::io::ErrorKind::NotFound {
+                println!("Could not read {}: {}", path.display(), e);
+            }
+            return scores;
+        }
+
+        let mut lines = contents.lines().enumerate();
+
+        if lines.next().map(|(_, line)| line.trim()) != Some(&format!("{} {}", MAGIC, VERSION)) {
+            println!("{} is not a scores file of version {}, ignoring it", path.display(), VERSION);
+            return scores;
+        }
+
+        for (i, line) in lines {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            match HighScores::parse_line(line) {
+                Ok((mode, entry)) => scores.tables.entry(mode).or_insert(vec![]).push(entry),
+                Err(e) => println!("{}:{}: {}", path.display(), i + 1, e),
+            }
+        }
+
+        // Whatever was written to the file, keep the tables in order.
+        for table in scores.tables.values_mut() {
+            table.sort_by(|a, b| b.score.cmp(&a.score));
+            table.truncate(MAX_ENTRIES);
+        }
+
+        scores
+    }
+
+    fn parse_line(line: &str) -> Result<(String, Entry), String> {
+        let fields: Vec<_> = line.split('\t').collect();
+        if fields.len() != 3 {
+            return Err("expected `<mode>\\t<score>\\t<name>`".to_string());
+        }
+
+        let score = fields[1].parse()
+            .map_err(|_| format!("invalid score `{}`", fields[1]))?;
+
+        Ok((fields[0].to_string(), Entry {
+            name: fields[2].to_string(),
+            score: score,
+        }))
+    }
+
+    /// Writes the scores back to the file they were loaded from.
+    pub fn save(&self) -> Result<(), String> {
+        let path = match self.path {
+            Some(ref path) => path,
+            None => return Err("no file to save the scores to".to_string()),
+        };
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+
+        let mut contents = format!("{} {}\n", MAGIC, VERSION);
+        for (mode, table) in &self.tables {
+            for entry in table {
+                contents.push_str(&format!("{}\t{}\t{}\n", mode, entry.score, entry.name));
+            }
+        }
+
+        File::create(path)
+            .and_then(|mut f| f.write_all(contents.as_bytes()))
+            .map_err(|e| format!("could not write {}: {}", path.display(), e))
+    }
+
+    /// The best scores of `mode`, from best to worst.
+    pub fn entries(&self, mode: &str) -> &[Entry] {
+        self.tables.get(mode).map(|table| &table[..]).unwrap_or(&[])
+    }
+
+    /// Whether `score` would make it into the table of `mode`.
+    pub fn qualifies(&self, mode: &str, score: u32) -> bool {
+        let table = self.entries(mode);
+        score > 0 && (table.len() < MAX_ENTRIES || table.iter().any(|entry| score > entry.score))
+    }
+
+    /// Adds a score to the table of `mode`, and returns its rank, starting
+    /// at 0, if it was good enough to be kept.
+    pub fn insert(&mut self, mode: &str, name: String, score: u32) -> Option<usize> {
+        let table = self.tables.entry(mode.to_string()).or_insert(vec![]);
+
+        //? Ties go to whoever got the score first.
+        let rank = table.iter().position(|entry| score > entry.score).unwrap_or(table.len());
+        if rank >= MAX_ENTRIES {
+            return None;
+        }
+
+        table.insert(rank, Entry { name: name, score: score });
+        table.truncate(MAX_ENTRIES);
+        Some(rank)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::io::Write;
+    use std::path::PathBuf;
+    use super::{HighScores, MAGIC, MAX_ENTRIES, VERSION};
+
+    /// Writes a scores file with the given contents, and returns its path.
+    fn write(name: &str, contents: &str) -> PathBuf {
+        let path = ::std::env::temp_dir().join(format!("scores-test-{}.txt", name));
+        File::create(&path).unwrap().write_all(contents.as_bytes()).unwrap();
+        path
+    }
+
+    fn scores(scores: &HighScores, mode: &str) -> Vec<u32> {
+        scores.entries(mode).iter().map(|entry| entry.score).collect()
+    }
+
+    #[test]
+    fn damaged_lines_are_skipped() {
+        let path = write("damaged", &format!(
+            "{} {}\n3_lives\t120\tANN\nnonsense\n3_lives\tlots\tBOB\n\n5_lives\t300\tCID\n",
+            MAGIC, VERSION));
+        let loaded = HighScores::load(&path);
+
+        assert_eq!(scores(&loaded, "3_lives"), vec![120]);
+        assert_eq!(scores(&loaded, "5_lives"), vec![300]);
+        assert_eq!(loaded.entries("5_lives")[0].name, "CID");
+    }
+
+    #[test]
+    fn other_versions_and_missing_files_start_empty() {
+        let path = write("version", &format!("{} {}\n3_lives\t120\tANN\n", MAGIC, VERSION + 1));
+        assert!(HighScores::load(&path).entries("3_lives").is_empty());
+
+        //? Not even text.
+        let path = write("binary", "");
+        File::create(&path).unwrap().write_all(&[0xff, 0xfe, 0x00, 0x9f]).unwrap();
+        assert!(HighScores::load(&path).entries("3_lives").is_empty());
+
+        let path = ::std::env::temp_dir().join("scores-test-missing.txt");
+        let _ = ::std::fs::remove_file(&path);
+        assert!(HighScores::load(&path).entries("3_lives").is_empty());
+    }
+
+    #[test]
+    fn loaded_tables_are_sorted_and_capped() {
+        let mut contents = format!("{} {}\n", MAGIC, VERSION);
+        for score in 1..MAX_ENTRIES as u32 + 5 {
+            contents.push_str(&format!("3_lives\t{}\tANN\n", score * 10));
+        }
+        let loaded = HighScores::load(&write("capped", &contents));
+
+        let expected: Vec<_> = (5..MAX_ENTRIES as u32 + 5).rev().map(|score| score * 10).collect();
+        assert_eq!(scores(&loaded, "3_lives"), expected);
+    }
+
+    #[test]
+    fn only_better_scores_qualify_once_the_table_is_full() {
+        let mut table = HighScores::load(&write("qualifies", ""));
+        assert!(table.qualifies("3_lives", 1));
+        assert!(!table.qualifies("3_lives", 0));
+
+        for score in 1..MAX_ENTRIES as u32 + 1 {
+            table.insert("3_lives", "ANN".to_string(), score * 10);
+        }
+
+        assert!(!table.qualifies("3_lives", 10));
+        assert!(table.qualifies("3_lives", 11));
+        //? Every mode has a table of its own.
+        assert!(table.qualifies("5_lives", 1));
+    }
+
+    #[test]
+    fn insert_ranks_and_caps_the_table() {
+        let mut table = HighScores::load(&write("insert", ""));
+
+        assert_eq!(table.insert("3_lives", "ANN".to_string(), 100), Some(0));
+        assert_eq!(table.insert("3_lives", "BOB".to_string(), 300), Some(0));
+        //? Ties go to whoever got the score first.
+        assert_eq!(table.insert("3_lives", "CID".to_string(), 100), Some(2));
+        assert_eq!(table.entries("3_lives")[1].name, "ANN");
+
+        for _ in 0..MAX_ENTRIES {
+            table.insert("3_lives", "DAN".to_string(), 200);
+        }
+        assert_eq!(table.entries("3_lives").len(), MAX_ENTRIES);
+        assert_eq!(table.insert("3_lives", "EVE".to_string(), 50), None);
+        assert_eq!(scores(&table, "3_lives")[MAX_ENTRIES - 1], 200);
+    }
+
+    #[test]
+    fn saved_scores_load_back() {
+        let path = write("round-trip", "");
+        let mut table = HighScores::load(&path);
+        table.insert("3_lives", "ANN".to_string(), 120);
+        table.insert("5_lives", "BOB".to_string(), 300);
+        table.save().unwrap();
+
+        let loaded = HighScores::load(&path);
+        assert_eq!(scores(&loaded, "3_lives"), vec![120]);
+        assert_eq!(loaded.entries("5_lives")[0].name, "BOB");
+    }
+}
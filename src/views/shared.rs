@@ -1,32 +1,66 @@
+use phi::Phi;
+use phi::assets::AssetError;
 use phi::data::Rectangle;
 use phi::gfx::{CopySprite, Sprite};
+use sdl2::pixels::Color;
 use sdl2::render::Renderer;
 
+//? This is the only font in `assets/`. It has no CJK glyphs, so every label
+//? drawn with it must be written in latin characters.
+pub const MENU_FONT: &'static str = "belligerent.ttf";
+
+pub const BG_BACK_PATH: &'static str = "starBG.png";
+pub const BG_MIDDLE_PATH: &'static str = "starMG.png";
+pub const BG_FRONT_PATH: &'static str = "starFG.png";
+
 #[derive(Clone)]
 pub struct Background {
     pub pos: f64,
+    // The position before the last update, used to draw between updates.
+    pub prev_pos: f64,
     // The amount of pixels moved to the left every second
     pub vel: f64,
     pub sprite: Sprite,
 }
 
 impl Background {
-    pub fn render(&mut self, renderer: &mut Renderer, elapsed: f64) {
+    pub fn new(sprite: Sprite, vel: f64) -> Background {
+        Background {
+            pos: 0.0,
+            prev_pos: 0.0,
+            vel: vel,
+            sprite: sprite,
+        }
+    }
+
+    pub fn update(&mut self, dt: f64) {
         // We define a logical position as depending solely on the time and the
         // dimensions of the image, not on the screen's size.
         let size = self.sprite.size();
-        self.pos += self.vel * elapsed;
+        self.prev_pos = self.pos;
+        self.pos += self.vel * dt;
         if self.pos > size.0 {
+            //? Wrap both positions so that interpolating between them still
+            //? moves to the left.
             self.pos -= size.0;
+            self.prev_pos -= size.0;
         }
+    }
 
-        // We determine the scale ratio of the window to the sprite.
-        let (win_w, win_h) = renderer.output_size().unwrap();
+    pub fn render(&self, renderer: &mut Renderer, alpha: f64) {
+        let size = self.sprite.size();
+        let mut pos = self.prev_pos + (self.pos - self.prev_pos) * alpha;
+        if pos < 0.0 {
+            pos += size.0;
+        }
+
+        // We determine the scale ratio of the screen to the sprite.
+        let (win_w, win_h) = renderer.logical_size();
         let scale = win_h as f64 / size.1;
 
         // We render as many copies of the background as necessary to fill
         // the screen.
-        let mut physical_left = -self.pos * scale;
+        let mut physical_left = -pos * scale;
 
         while physical_left < win_w as f64 {
             //? While the left of the image is still inside of the window...
@@ -52,23 +86,146 @@ pub struct BgSet {
 }
 
 impl BgSet {
-    pub fn new(renderer: &mut Renderer) -> BgSet {
-        BgSet {
-            back: Background {
-                pos: 0.0,
-                vel: 20.0,
-                sprite: Sprite::load(renderer, "assets/starBG.png").unwrap(),
-            },
-            middle: Background {
-                pos: 0.0,
-                vel: 40.0,
-                sprite: Sprite::load(renderer, "assets/starMG.png").unwrap(),
-            },
-            front: Background {
-                pos: 0.0,
-                vel: 80.0,
-                sprite: Sprite::load(renderer, "assets/starFG.png").unwrap(),
-            },
+    pub fn new(phi: &mut Phi) -> Result<BgSet, AssetError> {
+        Ok(BgSet {
+            back: Background::new(phi.assets.sprite(&phi.renderer, BG_BACK_PATH)?, 20.0),
+            middle: Background::new(phi.assets.sprite(&phi.renderer, BG_MIDDLE_PATH)?, 40.0),
+            front: Background::new(phi.assets.sprite(&phi.renderer, BG_FRONT_PATH)?, 80.0),
+        })
+    }
+
+    /// Scrolls every layer.
+    pub fn update(&mut self, dt: f64) {
+        self.back.update(dt);
+        self.middle.update(dt);
+        self.front.update(dt);
+    }
+}
+
+
+/// A column of labels in a colored box, one of which is selected using the
+/// directional keys or the mouse.
+pub struct Menu {
+    /// The sprites rendered for every label, when the player does not and
+    /// when they do focus on it, respectively.
+    labels: Vec<(Sprite, Sprite)>,
+    selected: usize,
+}
+
+impl Menu {
+    pub fn new(phi: &mut Phi, labels: &[&str]) -> Result<Menu, AssetError> {
+        let mut sprites = Vec::with_capacity(labels.len());
+
+        for label in labels {
+            sprites.push((
+                phi.ttf_str_sprite(label, MENU_FONT, 32, Color::RGB(220, 220, 220))?,
+                phi.ttf_str_sprite(label, MENU_FONT, 42, Color::RGB(255, 255, 255))?,
+            ));
+        }
+
+        Ok(Menu {
+            labels: sprites,
+            selected: 0,
+        })
+    }
+
+    /// Changes the selected label following the player's input, and returns
+    /// the index of the label which was chosen, if any.
+    pub fn update(&mut self, phi: &Phi) -> Option<usize> {
+        // Select the label under the mouse, if the mouse moved. Clicking a
+        // label chooses it.
+        let hovered = self.label_rects(phi).into_iter()
+            .position(|rect| rect.contains_point(phi.events.mouse));
+
+        if let Some(i) = hovered {
+            if phi.events.now.mouse_moved || phi.events.now.mouse_left == Some(true) {
+                self.selected = i;
+            }
+        }
+
+        if phi.events.now.confirm == Some(true) ||
+            (phi.events.now.mouse_left == Some(true) && hovered.is_some()) {
+            return Some(self.selected);
+        }
+
+        // Change the selected label using the keyboard, going 'round at
+        // either end.
+        if phi.events.now.move_up == Some(true) {
+            self.selected = (self.selected + self.labels.len() - 1) % self.labels.len();
+        }
+
+        if phi.events.now.move_down == Some(true) {
+            self.selected = (self.selected + 1) % self.labels.len();
+        }
+
+        None
+    }
+
+    /// Where every label is rendered. This is also what the mouse is tested
+    /// against.
+    fn label_rects(&self, phi: &Phi) -> Vec<Rectangle> {
+        let (win_w, win_h) = phi.output_size();
+        let label_h = 50.0;//每行的高
+        let box_h = self.labels.len() as f64 * label_h;//高度
+
+        self.labels.iter().enumerate().map(|(i, &(ref idle, ref hover))| {
+            let (w, h) = if self.selected == i { hover.size() } else { idle.size() };
+
+            Rectangle {
+                w: w,
+                h: h,
+                x: (win_w - w) / 2.0,
+                y: (win_h - box_h + label_h - h) / 2.0 + label_h * i as f64,
+            }
+        }).collect()
+    }
+
+    /// The height at which the border of the menu's box starts, so that
+    /// views may lay out what they show above it.
+    pub fn top(&self, phi: &Phi) -> f64 {
+        let (_, win_h) = phi.output_size();
+        let label_h = 50.0;//每行的高
+        let border_width = 3.0;//边框
+        let box_h = self.labels.len() as f64 * label_h;//高度
+        let margin_h = 10.0;//上填充
+
+        (win_h - box_h) / 2.0 - margin_h - border_width
+    }
+
+    pub fn render(&self, phi: &mut Phi) {
+        // Definitions for the menu's layout
+        let (win_w, win_h) = phi.output_size();
+        let label_h = 50.0;//每行的高
+        let border_width = 3.0;//边框
+        let box_w = 360.0;//宽度
+        let box_h = self.labels.len() as f64 * label_h;//高度
+        let margin_h = 10.0;//上填充
+
+        // Render the border of the colored box which holds the labels
+        phi.renderer.set_draw_color(Color::RGB(70, 15, 70));
+        phi.renderer.fill_rect(Rectangle {
+            w: box_w + border_width * 2.0,
+            h: box_h + border_width * 2.0 + margin_h * 2.0,
+            x: (win_w - box_w) / 2.0 - border_width,
+            y: self.top(phi),
+        }.to_sdl().unwrap());
+
+        // Render the colored box which holds the labels
+        phi.renderer.set_draw_color(Color::RGB(140, 30, 140));
+        phi.renderer.fill_rect(Rectangle {
+            w: box_w,
+            h: box_h + margin_h * 2.0,
+            x: (win_w - box_w) / 2.0,
+            y: (win_h - box_h) / 2.0 - margin_h,
+        }.to_sdl().unwrap());
+
+        let rects = self.label_rects(phi);
+        for (i, (&(ref idle, ref hover), rect)) in self.labels.iter().zip(rects).enumerate() {
+            if self.selected == i {
+                phi.renderer.copy_sprite(hover, rect);
+            } else {
+                phi.renderer.copy_sprite(idle, rect);
+            }
         }
     }
-}
\ No newline at end of file
+}
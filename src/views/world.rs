@@ -0,0 +1,943 @@
+use phi::Events;
+use phi::data::{Rectangle, seeded_rng};
+use rand::{Rng, XorShiftRng};
+use phi::broadphase::SpatialHash;
+use phi::ecs::{Appearance, Body, Components, Entity, Lifetime, Look, Scene, Velocity};
+use phi::gfx::{Animation, Playback};
+use phi::shapes::{Collider, Hitbox, Mask};
+use std::rc::Rc;
+use views::bullets::*;
+use views::powerups::*;
+
+/// Pixels traveled by the player's ship every second, when it is moving.
+const PLAYER_SPEED: f64 = 180.0;
+pub const PLAYER_W: f64 = 43.0;
+pub const PLAYER_H: f64 = 39.0;
+
+/// Seconds between the destruction of a ship and the arrival of the next one.
+const RESPAWN_DELAY: f64 = 2.0;
+/// Seconds during which a ship which just arrived cannot be destroyed.
+const INVULNERABILITY: f64 = 3.0;
+
+/// The side of the largest asteroids, which are drawn at the size of their
+/// frames. The smallest are half as large.
+pub const ASTEROID_SIDE: f64 = 96.0;
+const MIN_ASTEROID_SIDE: f64 = ASTEROID_SIDE / 2.0;
+pub const EXPLOSION_SIDE: f64 = 96.0;
+
+/// The points earned by shooting an asteroid of side `ASTEROID_SIDE` which
+/// moves 100 pixels every second. Faster and smaller asteroids are harder to
+/// hit, and are worth proportionally more.
+const ASTEROID_POINTS: f64 = 100.0;
+/// The points earned for every second the player survives.
+const SURVIVAL_POINTS: f64 = 10.0;
+
+/// The side of the cells in which bullets are sorted to find those which an
+/// asteroid might hit. Asteroids then cover at most four cells.
+const BROADPHASE_CELL: f64 = ASTEROID_SIDE;
+
+/// Seconds of survival after which the next wave begins, with more asteroids.
+const WAVE_DURATION: f64 = 30.0;
+
+/// The average time, in seconds, between two asteroids during the first wave,
+/// that is, a bit less than two seconds. Every wave brings them this much
+/// sooner, down to `MIN_SPAWN_INTERVAL`.
+const SPAWN_INTERVAL: f64 = 5.0 / 3.0;
+const SPAWN_INTERVAL_STEP: f64 = 1.0 / 6.0;
+const MIN_SPAWN_INTERVAL: f64 = 2.0 / 3.0;
+
+/// The different states our ship might be in. In the image, they're ordered
+/// from left to right, then from top to bottom.
+#[derive(Clone, Copy)]
+pub enum PlayerFrame {
+    UpNorm   = 0,
+    UpFast   = 1,
+    UpSlow   = 2,
+    MidNorm  = 3,
+    MidFast  = 4,
+    MidSlow  = 5,
+    DownNorm = 6,
+    DownFast = 7,
+    DownSlow = 8
+}
+
+/// What the world needs to know of the art: how the asteroids and explosions
+/// are animated, as described by their spritesheets, and which pixels of the
+/// frames are solid.
+pub struct WorldArt {
+    pub asteroid: Animation,
+    pub explosion: Animation,
+
+    /// The masks of the ship, in the order of `PlayerFrame`.
+    pub player_masks: Vec<Rc<Mask>>,
+
+    /// The masks of the asteroid, for every frame of its animation.
+    pub asteroid_masks: Vec<Rc<Mask>>,
+}
+
+/// The shapes with which every type of entity collides.
+#[derive(Clone, Copy, Debug)]
+pub struct Hitboxes {
+    pub player: Hitbox,
+    pub asteroid: Hitbox,
+    pub bullet: Hitbox,
+}
+
+impl Default for Hitboxes {
+    fn default() -> Hitboxes {
+        Hitboxes {
+            //? The ship is far from rectangular, so its pixels are used.
+            player: Hitbox::Mask,
+            //? Asteroids are round, and their frames have empty corners.
+            asteroid: Hitbox::Circle(0.8),
+            bullet: Hitbox::Rect,
+        }
+    }
+}
+
+/// The state of the controls for a single step of the simulation, whatever
+/// produced it.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WorldInput {
+    pub up: bool,
+    pub down: bool,
+    pub left: bool,
+    pub right: bool,
+
+    /// The direction of an analog stick, whose length scales the speed of
+    /// the ship. It overrides the directions above unless it is `(0, 0)`.
+    pub stick: (f64, f64),
+
+    /// Whether the player asked to shoot during this step.
+    pub fire: bool,
+
+    /// Whether the player asked for the next weapon during this step.
+    pub switch_weapon: bool,
+}
+
+impl WorldInput {
+    /// Reads the controls from the actions gathered by `Events::pump`.
+    pub fn from_events(events: &Events) -> WorldInput {
+        WorldInput {
+            up: events.move_up,
+            down: events.move_down,
+            left: events.move_left,
+            right: events.move_right,
+            stick: events.stick,
+            fire: events.now.fire == Some(true),
+            switch_weapon: events.now.switch_weapon == Some(true),
+        }
+    }
+}
+
+/// Something which happened during a step of the simulation and which the
+/// view may want to react to, e.g. by playing a sound.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WorldEvent {
+    ShotFired,
+    AsteroidDestroyed,
+    PlayerDestroyed,
+    PowerUpCollected(PowerUp),
+
+    /// The last ship of the player was destroyed.
+    GameOver,
+}
+
+/// How a game went, e.g. to be shown once it is over.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RunStats {
+    /// How long the player survived, in seconds.
+    pub time: f64,
+    pub shots_fired: u32,
+    pub asteroids_destroyed: u32,
+
+    /// The points earned by shooting asteroids.
+    pub points: u32,
+}
+
+impl RunStats {
+    /// The points earned by shooting asteroids, plus a bonus for the time
+    /// survived.
+    pub fn score(&self) -> u32 {
+        self.points + (self.time * SURVIVAL_POINTS) as u32
+    }
+}
+
+pub struct Player {
+    pub rect: Rectangle,
+    /// Where the ship was before the last step.
+    prev: Rectangle,
+    pub current: PlayerFrame,
+
+    /// Whether the ship is still there. Once it has been destroyed, it is no
+    /// longer drawn and ignores the controls.
+    pub alive: bool,
+
+    /// The time left, in seconds, during which asteroids go through the ship.
+    pub invulnerable: f64,
+
+    pub weapon: Weapon,
+
+    /// The power-ups currently in effect, which are lost with the ship.
+    pub effects: Effects,
+}
+
+impl Player {
+    pub fn new(bounds: Rectangle) -> Player {
+        // Spawn the player at the center of the screen, vertically.
+        let rect = Rectangle {
+            x: bounds.x + 64.0,
+            y: bounds.y + (bounds.h - PLAYER_H) / 2.0,
+            w: PLAYER_W,
+            h: PLAYER_H,
+        };
+
+        Player {
+            rect: rect,
+            prev: rect,
+            current: PlayerFrame::MidNorm,
+            alive: true,
+            invulnerable: 0.0,
+            weapon: Weapon::Twin,
+            effects: Effects::default(),
+        }
+    }
+
+    /// Where the ship should be drawn, `alpha` of the way through the next
+    /// step.
+    pub fn rect_at(&self, alpha: f64) -> Rectangle {
+        self.prev.lerp(self.rect, alpha)
+    }
+
+    fn update(&mut self, input: &WorldInput, bounds: Rectangle, elapsed: f64) {
+        // Move the player's ship
+        self.prev = self.rect;
+
+        let speed =
+            if self.effects.is_active(PowerUp::Speed) { PLAYER_SPEED * SPEED_BOOST }
+            else { PLAYER_SPEED };
+
+        let (dx, dy) =
+            if input.stick != (0.0, 0.0) {
+                // The further the stick is pushed, the faster the ship goes.
+                (input.stick.0 * speed * elapsed,
+                 input.stick.1 * speed * elapsed)
+            } else {
+                let diagonal =
+                (input.up ^ input.down) &&
+                    (input.left ^ input.right);
+
+                let moved =
+                if diagonal { 1.0 / 2.0f64.sqrt() }
+                    else { 1.0 } * speed * elapsed;
+
+                let dx = match (input.left, input.right) {
+                    (true, true) | (false, false) => 0.0,
+                    (true, false) => -moved,
+                    (false, true) => moved,
+                };
+
+                let dy = match (input.up, input.down) {
+                    (true, true) | (false, false) => 0.0,
+                    (true, false) => -moved,
+                    (false, true) => moved,
+                };
+
+                (dx, dy)
+            };
+
+        self.rect.x += dx;
+        self.rect.y += dy;
+
+        // The movable region spans the entire height of the window and 70% of its
+        // width. This way, the player cannot get to the far right of the screen, where
+        // we will spawn the asteroids, and get immediately eliminated.
+        //
+        // We restrain the width because most screens are wider than they are high.
+        let movable_region = Rectangle {
+            w: bounds.w * 0.70,
+            ..bounds
+        };
+
+        // If the player cannot fit in the screen, then there is a problem and
+        // the game should be promptly aborted.
+        self.rect = self.rect.move_inside(movable_region).unwrap();
+
+        // Select the appropriate sprite of the ship to show.
+        self.current =
+            if dx == 0.0 && dy < 0.0       { PlayerFrame::UpNorm }
+                else if dx > 0.0 && dy < 0.0   { PlayerFrame::UpFast }
+                    else if dx < 0.0 && dy < 0.0   { PlayerFrame::UpSlow }
+                        else if dx == 0.0 && dy == 0.0 { PlayerFrame::MidNorm }
+                            else if dx > 0.0 && dy == 0.0  { PlayerFrame::MidFast }
+                                else if dx < 0.0 && dy == 0.0  { PlayerFrame::MidSlow }
+                                    else if dx == 0.0 && dy > 0.0  { PlayerFrame::DownNorm }
+                                        else if dx > 0.0 && dy > 0.0   { PlayerFrame::DownFast }
+                                            else if dx < 0.0 && dy > 0.0   { PlayerFrame::DownSlow }
+                                                else { unreachable!() };
+    }
+
+    fn spawn_bullets(&self) -> Vec<(Rectangle, Box<Bullet>)> {
+        let cannons_x = self.rect.x + 30.0;
+        let cannon1_y = self.rect.y + 6.0;
+        let cannon2_y = self.rect.y + PLAYER_H - 10.0;
+        let bullets = spawn_bullets(self.weapon, cannons_x, cannon1_y, cannon2_y);
+
+        if self.effects.is_active(PowerUp::WeaponUpgrade) {
+            bullets.into_iter()
+                .map(|(rect, bullet)| upgrade(rect, bullet))
+                .collect()
+        } else {
+            bullets
+        }
+    }
+}
+
+/// The art drawn by the world's entities, as indices into the list given to
+/// `Scene::render`.
+pub const ASTEROID_ART: usize = 0;
+pub const EXPLOSION_ART: usize = 1;
+
+/// The layers on which entities are drawn, from the bottom up.
+const BULLET_LAYER: i32 = 0;
+const POWERUP_LAYER: i32 = 1;
+const ASTEROID_LAYER: i32 = 2;
+const EXPLOSION_LAYER: i32 = 3;
+
+/// Something which the player gets points for shooting down.
+#[derive(Clone, Copy, Debug)]
+pub struct Target {
+    pub points: u32,
+}
+
+//小行星
+struct AsteroidFactory {
+    anim: Animation,
+}
+
+impl AsteroidFactory {
+    /// Spawns an asteroid at a random height, over the right of the screen,
+    /// drifting left, and tells what it is worth.
+    fn random(&self, rng: &mut XorShiftRng, bounds: Rectangle, hitbox: Hitbox,
+              scene: &mut Scene) -> (Entity, Target) {
+        // Between half and one and a half times as fast as the sheet says
+        let mut anim = self.anim;
+        anim.set_fps(self.anim.fps() * (rng.gen::<f64>() + 0.5));
+
+        // side in [MIN_ASTEROID_SIDE, ASTEROID_SIDE)
+        //? The frames, masks and hitbox are all stretched over the rectangle,
+        //? so they shrink along with it.
+        let side = MIN_ASTEROID_SIDE + rng.gen::<f64>() * (ASTEROID_SIDE - MIN_ASTEROID_SIDE);
+
+        // In the screen vertically, and over the right of the screen
+        // horizontally.
+        let rect = Rectangle {
+            w: side,
+            h: side,
+            x: bounds.x + bounds.w,
+            y: bounds.y + rng.gen::<f64>() * (bounds.h - side),
+        };
+
+        // vel in [50.0, 150.0)
+        let vel = rng.gen::<f64>() * 100.0 + 50.0;
+
+        // Faster and smaller asteroids are worth more.
+        let points = (ASTEROID_POINTS * vel / 100.0 * ASTEROID_SIDE / rect.w).round() as u32;
+
+        let asteroid = scene.spawn();
+        scene.bodies.insert(asteroid, Body::new(rect));
+        scene.velocities.insert(asteroid, Velocity(-vel, 0.0));
+        scene.animations.insert(asteroid, anim);
+        scene.appearances.insert(asteroid,
+            Appearance::new(Look::Frames { art: ASTEROID_ART, frame: 0 }, ASTEROID_LAYER));
+        scene.hitboxes.insert(asteroid, hitbox);
+        //? The asteroid starts out of the screen, so it is only gone once it
+        //? has left on the other side.
+        scene.lifetimes.insert(asteroid, Lifetime::InBounds(ASTEROID_SIDE));
+        (asteroid, Target { points: points })
+    }
+}
+
+struct ExplosionFactory {
+    anim: Animation,
+}
+
+impl ExplosionFactory {
+    fn new(mut anim: Animation) -> ExplosionFactory {
+        //? The explosion is destroyed once its animation is finished.
+        anim.set_playback(Playback::Once);
+
+        ExplosionFactory {
+            anim: anim,
+        }
+    }
+
+    /// Spawns an explosion centered on `center`, which fades away during its
+    /// second half.
+    fn at_center(&self, scene: &mut Scene, rng: &mut XorShiftRng, center: (f64, f64)) -> Entity {
+        let rect = Rectangle::with_size(EXPLOSION_SIDE, EXPLOSION_SIDE).center_at(center);
+        let mut appearance =
+            Appearance::new(Look::Frames { art: EXPLOSION_ART, frame: 0 }, EXPLOSION_LAYER);
+        appearance.fade_out = true;
+
+        //? Every explosion is turned and flipped at random, so that those
+        //? which happen together do not look all the same.
+        appearance.transform = appearance.transform
+            .rotated(rng.gen::<f64>() * 360.0)
+            .flipped(rng.gen(), rng.gen());
+
+        let explosion = scene.spawn();
+        scene.bodies.insert(explosion, Body::new(rect));
+        scene.animations.insert(explosion, self.anim);
+        scene.appearances.insert(explosion, appearance);
+        scene.lifetimes.insert(explosion, Lifetime::Animation);
+        explosion
+    }
+}
+
+/// Everything that takes part in a game, and the rules which make it move.
+///
+/// The world knows nothing about windows, textures or sounds: it is advanced
+/// by `step` and left to some view to draw and to make noise about.
+pub struct GameWorld {
+    pub player: Player,
+
+    /// The bullets, asteroids and explosions, and how to draw them.
+    pub scene: Scene,
+
+    /// How the bullets in the scene behave.
+    pub bullets: Components<Box<Bullet>>,
+
+    /// What the entities of the scene which can be shot are worth.
+    pub targets: Components<Target>,
+
+    /// The power-ups drifting in the scene, waiting to be picked up.
+    pub pickups: Components<PowerUp>,
+
+    pub stats: RunStats,
+
+    /// The number of ships left to the player, including the current one.
+    pub lives: u32,
+
+    /// The time left before the next ship arrives, if the last one was
+    /// destroyed.
+    respawn_in: Option<f64>,
+
+    /// How the entities collide. These are given to the entities as they
+    /// spawn, and may be changed at any time.
+    pub hitboxes: Hitboxes,
+
+    asteroid_factory: AsteroidFactory,
+    explosion_factory: ExplosionFactory,
+    player_masks: Vec<Rc<Mask>>,
+
+    /// The masks of the frames of every art, by their index, e.g.
+    /// `ASTEROID_ART`.
+    masks: Vec<Vec<Rc<Mask>>>,
+
+    /// The targets and bullets, registered anew during every step.
+    grid: SpatialHash<Entity>,
+
+    /// The time left before the next asteroid arrives, in seconds.
+    next_asteroid_in: f64,
+
+    /// The region in which the game takes place.
+    bounds: Rectangle,
+
+    /// The source of every random decision taken by the world. Two worlds
+    /// created with the same seed and given the same inputs stay identical.
+    rng: XorShiftRng,
+}
+
+impl GameWorld {
+    /// Creates an empty world of the given size, with the player at its
+    /// spawn point and `lives` ships to play with.
+    pub fn new(size: (f64, f64), seed: u64, lives: u32, art: WorldArt) -> GameWorld {
+        let bounds = Rectangle::with_size(size.0, size.1);
+
+        let mut masks = vec![vec![]; 2];
+        masks[ASTEROID_ART] = art.asteroid_masks;
+
+        let mut world = GameWorld {
+            player: Player::new(bounds),
+            scene: Scene::new(),
+            bullets: Components::new(),
+            targets: Components::new(),
+            pickups: Components::new(),
+            asteroid_factory: AsteroidFactory { anim: art.asteroid },
+            explosion_factory: ExplosionFactory::new(art.explosion),
+            hitboxes: Hitboxes::default(),
+            player_masks: art.player_masks,
+            masks: masks,
+            grid: SpatialHash::new(BROADPHASE_CELL),
+            stats: RunStats::default(),
+            lives: lives,
+            respawn_in: None,
+            bounds: bounds,
+            next_asteroid_in: 0.0,
+            rng: seeded_rng(seed),
+        };
+
+        world.next_asteroid_in = world.spawn_delay();
+        world
+    }
+
+    pub fn bounds(&self) -> Rectangle {
+        self.bounds
+    }
+
+    /// The wave the player is in, starting at 1. Every wave brings more
+    /// asteroids than the previous one.
+    pub fn wave(&self) -> u32 {
+        1 + (self.stats.time / WAVE_DURATION) as u32
+    }
+
+    /// Draws the time until the next asteroid, which comes sooner with every
+    /// wave.
+    //? The delays are exponentially distributed, so that asteroids are as
+    //? likely to arrive at any moment, however long the last one took.
+    fn spawn_delay(&mut self) -> f64 {
+        let waves = (self.wave() - 1) as f64;
+        let mean = (SPAWN_INTERVAL - SPAWN_INTERVAL_STEP * waves).max(MIN_SPAWN_INTERVAL);
+        -mean * (1.0 - self.rng.gen::<f64>()).ln()
+    }
+
+    /// Removes `entity` from the scene, along with the components which only
+    /// the world knows about.
+    fn despawn(&mut self, entity: Entity) {
+        self.scene.despawn(entity);
+        self.bullets.remove(entity);
+        self.targets.remove(entity);
+        self.pickups.remove(entity);
+    }
+
+    /// Fires `bullet` from `rect`.
+    fn spawn_bullet(&mut self, rect: Rectangle, bullet: Box<Bullet>) -> Entity {
+        let angle = bullet.angle();
+        let mut appearance = Appearance::new(bullet.look(), BULLET_LAYER);
+        appearance.transform = appearance.transform.rotated(angle);
+
+        //? Bullets fired at an angle are turned towards where they go, and
+        //? so is their hitbox.
+        let hitbox =
+            if angle == 0.0 { self.hitboxes.bullet }
+            else { self.hitboxes.bullet.rotated(angle) };
+
+        let entity = self.scene.spawn();
+        self.scene.bodies.insert(entity, Body::new(rect));
+        self.scene.appearances.insert(entity, appearance);
+        self.scene.hitboxes.insert(entity, hitbox);
+        //? Bullets are gone as soon as they have left the screen.
+        self.scene.lifetimes.insert(entity, Lifetime::InBounds(0.0));
+        self.bullets.insert(entity, bullet);
+        entity
+    }
+
+    /// Drops `powerup` at `center`, from where it drifts left.
+    fn spawn_powerup(&mut self, powerup: PowerUp, center: (f64, f64)) -> Entity {
+        let entity = self.scene.spawn();
+        self.scene.bodies.insert(entity, Body::new(PowerUp::rect_at(center)));
+        self.scene.velocities.insert(entity, Velocity(-POWERUP_SPEED, 0.0));
+        self.scene.appearances.insert(entity, Appearance::new(powerup.look(), POWERUP_LAYER));
+        self.scene.hitboxes.insert(entity, Hitbox::Rect);
+        self.scene.lifetimes.insert(entity, Lifetime::InBounds(POWERUP_SIDE));
+        self.pickups.insert(entity, powerup);
+        entity
+    }
+
+    /// What `entity` collides with, if it may collide.
+    fn collider<'a>(&'a self, entity: Entity) -> Option<Collider<'a>> {
+        let body = match self.scene.bodies.get(entity) {
+            Some(body) => body,
+            None => return None,
+        };
+
+        //? The mask is that of the frame being shown, if the art has masks.
+        let mask = match self.scene.appearances.get(entity).map(|appearance| appearance.look) {
+            Some(Look::Frames { art, frame }) =>
+                self.masks.get(art).and_then(|masks| masks.get(frame)).map(|mask| &**mask),
+            _ => None,
+        };
+
+        self.scene.hitboxes.get(entity).map(|hitbox| hitbox.collider(body.rect, mask))
+    }
+
+    /// Brings in the next ship at the spawn point, and destroys the
+    /// asteroids around it so that it gets a chance to move away.
+    fn respawn(&mut self) {
+        //? The next ship keeps the weapon which the player had chosen.
+        let weapon = self.player.weapon;
+        self.player = Player::new(self.bounds);
+        self.player.invulnerable = INVULNERABILITY;
+        self.player.weapon = weapon;
+
+        let spawn = self.player.rect;
+        let spawn_area = Rectangle {
+            x: spawn.x - ASTEROID_SIDE,
+            y: spawn.y - ASTEROID_SIDE,
+            w: spawn.w + ASTEROID_SIDE * 2.0,
+            h: spawn.h + ASTEROID_SIDE * 2.0,
+        };
+
+        for target in self.targets.entities() {
+            let rect = self.scene.bodies.get(target).unwrap().rect;
+            if rect.overlaps(spawn_area) {
+                self.despawn(target);
+                self.explosion_factory.at_center(&mut self.scene, &mut self.rng, rect.center());
+            }
+        }
+    }
+
+    /// Advances the simulation by `elapsed` seconds, given the state of the
+    /// controls. Returns what happened during this step, in order.
+    pub fn step(&mut self, input: &WorldInput, elapsed: f64) -> Vec<WorldEvent> {
+        let mut events = vec![];
+        let bounds = self.bounds;
+
+        // Update the player
+        if self.player.alive {
+            self.player.update(input, bounds, elapsed);
+            self.player.invulnerable = (self.player.invulnerable - elapsed).max(0.0);
+            self.player.effects.update(elapsed);
+            self.stats.time += elapsed;
+        }
+
+        // Bring in the next ship once it is time.
+        if let Some(delay) = self.respawn_in {
+            if delay <= elapsed {
+                self.respawn_in = None;
+                self.respawn();
+            } else {
+                self.respawn_in = Some(delay - elapsed);
+            }
+        }
+
+        // Update the bullets
+        for (entity, bullet) in self.bullets.iter_mut() {
+            if let Some(body) = self.scene.bodies.get_mut(entity) {
+                bullet.update(body, elapsed);
+            }
+        }
+
+        // Move and animate everything else, then forget what is gone.
+        self.scene.move_bodies(elapsed);
+        self.scene.animate(elapsed);
+
+        for entity in self.scene.expire(bounds) {
+            self.despawn(entity);
+        }
+
+        // Collision detection 碰撞检测
+        //? Only the bullets and targets which are close to each other are
+        //? tested, rather than every bullet against every target.
+        self.grid.clear();
+        for entity in self.targets.entities().into_iter().chain(self.bullets.entities()) {
+            if let Some(collider) = self.collider(entity) {
+                self.grid.insert(entity, collider.shape.bounds());
+            }
+        }
+
+        let mut player_hit = false;
+        let mut bullets_hit = vec![];
+        let mut targets_shot = vec![];
+        let mut targets_hit = vec![];
+
+        for (a, b) in self.grid.pairs() {
+            //? Targets are registered before bullets, so they come first.
+            //? Pairs of bullets, or of targets, never collide.
+            if !self.targets.contains(a) || !self.bullets.contains(b) {
+                continue;
+            }
+
+            let hit = match (self.collider(a), self.collider(b)) {
+                (Some(target), Some(bullet)) => target.overlaps(&bullet),
+                _ => false,
+            };
+
+            if hit {
+                targets_shot.push(a);
+
+                if !self.bullets.get(b).unwrap().pierces() {
+                    bullets_hit.push(b);
+                }
+            }
+        }
+
+        //? A target may be hit by several bullets, and a bullet may hit
+        //? several targets, during the same step.
+        targets_shot.sort();
+        targets_shot.dedup();
+        bullets_hit.sort();
+        bullets_hit.dedup();
+
+        {
+            let player = self.hitboxes.player.collider(
+                self.player.rect,
+                self.player_masks.get(self.player.current as usize).map(|mask| &**mask));
+            let player_vulnerable = self.player.alive && self.player.invulnerable <= 0.0;
+            //? Shielded ships break the asteroids which they run into, even
+            //? while they are invulnerable.
+            let player_shielded = self.player.alive && self.player.effects.is_active(PowerUp::Shield);
+
+            for target in self.targets.entities() {
+                let collider = match self.collider(target) {
+                    Some(collider) => collider,
+                    None => continue,
+                };
+
+                let shot = targets_shot.binary_search(&target).is_ok();
+
+                // The player's ship is destroyed if it is hit by an asteroid,
+                // unless it is shielded. In both cases, the asteroid is also
+                // destroyed.
+                let rammed = (player_vulnerable || player_shielded) && collider.overlaps(&player);
+                player_hit |= rammed && !player_shielded;
+
+                if shot || rammed {
+                    targets_hit.push((target, shot));
+                }
+            }
+        }
+
+        for bullet in bullets_hit {
+            self.despawn(bullet);
+        }
+
+        for (target, shot) in targets_hit {
+            if shot {
+                self.stats.asteroids_destroyed += 1;
+                self.stats.points += self.targets.get(target).unwrap().points;
+            }
+
+            // Spawn an explosive wherever an asteroid was destroyed.
+            let center = self.scene.bodies.get(target).unwrap().rect.center();
+            self.despawn(target);
+            self.explosion_factory.at_center(&mut self.scene, &mut self.rng, center);
+
+            // Asteroids which were shot down sometimes leave a power-up.
+            if shot {
+                if let Some(powerup) = PowerUp::random_drop(&mut self.rng) {
+                    self.spawn_powerup(powerup, center);
+                }
+            }
+
+            events.push(WorldEvent::AsteroidDestroyed);
+        }
+
+        // The ship explodes like the asteroids do.
+        if player_hit {
+            self.player.alive = false;
+            self.explosion_factory.at_center(&mut self.scene, &mut self.rng, self.player.rect.center());
+
+            events.push(WorldEvent::PlayerDestroyed);
+
+            self.lives = self.lives.saturating_sub(1);
+            if self.lives > 0 {
+                self.respawn_in = Some(RESPAWN_DELAY);
+            } else {
+                events.push(WorldEvent::GameOver);
+            }
+        }
+
+        // Pick up the power-ups which the ship flies over.
+        if self.player.alive {
+            for entity in self.pickups.entities() {
+                if self.scene.bodies.get(entity).unwrap().rect.overlaps(self.player.rect) {
+                    let powerup = *self.pickups.get(entity).unwrap();
+                    self.despawn(entity);
+
+                    match powerup {
+                        PowerUp::ExtraLife => self.lives += 1,
+                        _ => self.player.effects.start(powerup),
+                    }
+
+                    events.push(WorldEvent::PowerUpCollected(powerup));
+                }
+            }
+        }
+
+        // Allow the player to shoot after the bullets are updated, so that,
+        // when rendered for the first time, they are drawn wherever they
+        // spawned.
+        //
+        //? In this case, we ensure that the new bullets are drawn at the tips
+        //? of the cannons.
+        if input.switch_weapon && self.player.alive {
+            self.player.weapon = self.player.weapon.next();
+        }
+
+        if input.fire && self.player.alive {
+            for (rect, bullet) in self.player.spawn_bullets() {
+                self.spawn_bullet(rect, bullet);
+            }
+            self.stats.shots_fired += 1;
+            events.push(WorldEvent::ShotFired);
+        }
+
+        // Create the asteroids whose time has come. However long the step,
+        // as many of them arrive during the same time.
+        self.next_asteroid_in -= elapsed;
+        while self.next_asteroid_in <= 0.0 {
+            let (asteroid, target) = self.asteroid_factory.random(
+                &mut self.rng, bounds, self.hitboxes.asteroid, &mut self.scene);
+            self.targets.insert(asteroid, target);
+            self.next_asteroid_in += self.spawn_delay();
+        }
+
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A mask which is solid everywhere.
+    fn solid() -> Rc<Mask> {
+        Rc::new(Mask::from_alpha(&[255; 4], 2, Rectangle::with_size(2.0, 2.0)))
+    }
+
+    /// A world of 800x600 pixels in which every frame is solid.
+    fn world(seed: u64) -> GameWorld {
+        let art = WorldArt {
+            asteroid: Animation::with_fps(1, 10.0),
+            explosion: Animation::with_fps(4, 10.0),
+            player_masks: vec![solid(); 9],
+            asteroid_masks: vec![solid()],
+        };
+
+        GameWorld::new((800.0, 600.0), seed, 3, art)
+    }
+
+    /// Spawns an asteroid centered on `center`, and tells what it is worth.
+    fn asteroid_at(world: &mut GameWorld, center: (f64, f64)) -> (Entity, Target) {
+        let (asteroid, target) = world.asteroid_factory.random(
+            &mut world.rng, world.bounds, world.hitboxes.asteroid, &mut world.scene);
+        world.targets.insert(asteroid, target);
+
+        let rect = world.scene.bodies.get(asteroid).unwrap().rect.center_at(center);
+        world.scene.bodies.insert(asteroid, Body::new(rect));
+        (asteroid, target)
+    }
+
+    #[test]
+    fn ship_stays_in_the_left_of_the_screen() {
+        let mut world = world(1);
+        let movable = Rectangle { w: world.bounds().w * 0.70, ..world.bounds() };
+
+        let inputs = [
+            WorldInput { right: true, up: true, ..WorldInput::default() },
+            WorldInput { right: true, down: true, ..WorldInput::default() },
+            WorldInput { left: true, ..WorldInput::default() },
+        ];
+
+        for input in &inputs {
+            //? Five seconds are enough to cross the screen in any direction.
+            for _ in 0..300 {
+                //? Asteroids go through the ship, so that it keeps moving.
+                world.player.invulnerable = 1.0;
+                world.step(input, 1.0 / 60.0);
+                assert!(movable.contains(world.player.rect));
+            }
+        }
+
+        assert_eq!(world.player.rect.x, 0.0);
+        assert_eq!(world.player.rect.y + world.player.rect.h, 600.0);
+    }
+
+    #[test]
+    fn bullets_destroy_asteroids() {
+        let mut world = world(2);
+        let (asteroid, target) = asteroid_at(&mut world, (500.0, 300.0));
+
+        let (rect, bullet) = spawn_bullets(Weapon::Twin, 0.0, 0.0, 0.0).remove(0);
+        let bullet = world.spawn_bullet(rect.center_at((500.0, 300.0)), bullet);
+
+        let events = world.step(&WorldInput::default(), 0.001);
+
+        assert!(events.contains(&WorldEvent::AsteroidDestroyed));
+        assert!(!world.scene.is_alive(asteroid));
+        assert!(!world.scene.is_alive(bullet));
+        assert_eq!(world.stats.asteroids_destroyed, 1);
+        assert_eq!(world.stats.points, target.points);
+        assert!(world.player.alive);
+    }
+
+    #[test]
+    fn asteroids_destroy_vulnerable_ships() {
+        let mut world = world(3);
+        let center = world.player.rect.center();
+        let (asteroid, _) = asteroid_at(&mut world, center);
+
+        let events = world.step(&WorldInput::default(), 0.001);
+
+        assert!(events.contains(&WorldEvent::PlayerDestroyed));
+        assert!(!events.contains(&WorldEvent::GameOver));
+        assert!(!world.scene.is_alive(asteroid));
+        assert!(!world.player.alive);
+        assert_eq!(world.lives, 2);
+        //? Ramming an asteroid is not shooting it down.
+        assert_eq!(world.stats.asteroids_destroyed, 0);
+    }
+
+    #[test]
+    fn same_seed_and_inputs_give_the_same_game() {
+        let mut worlds = vec![world(42), world(42)];
+
+        //? Some made-up play: weaving up and down, shooting twice a second
+        //? and switching weapons now and then, for a full minute.
+        for tick in 0..3600 {
+            let input = WorldInput {
+                up: tick % 240 < 120,
+                down: tick % 240 >= 120,
+                right: tick % 90 < 30,
+                fire: tick % 30 == 0,
+                switch_weapon: tick % 600 == 0,
+                ..WorldInput::default()
+            };
+
+            let events: Vec<_> = worlds.iter_mut()
+                .map(|world| world.step(&input, 1.0 / 60.0))
+                .collect();
+            assert_eq!(events[0], events[1]);
+        }
+
+        let (a, b) = (&worlds[0], &worlds[1]);
+        assert!(a.stats.shots_fired > 0);
+        assert_eq!(a.stats.time, b.stats.time);
+        assert_eq!(a.stats.shots_fired, b.stats.shots_fired);
+        assert_eq!(a.stats.asteroids_destroyed, b.stats.asteroids_destroyed);
+        assert_eq!(a.stats.points, b.stats.points);
+        assert_eq!(a.lives, b.lives);
+        assert_eq!(a.player.rect, b.player.rect);
+
+        let bodies = |world: &GameWorld| world.scene.bodies.iter()
+            .map(|(entity, body)| (entity, body.rect))
+            .collect::<Vec<_>>();
+        assert!(!bodies(a).is_empty());
+        assert_eq!(bodies(a), bodies(b));
+    }
+
+    #[test]
+    fn angled_bullets_turn_the_configured_hitbox() {
+        let mut world = world(4);
+        world.hitboxes.bullet = Hitbox::Oriented { scale: (0.5, 0.5), angle: 0.0 };
+        world.player.weapon = Weapon::Spread;
+
+        world.step(&WorldInput { fire: true, ..WorldInput::default() }, 0.001);
+
+        let mut angles = vec![];
+        for bullet in world.bullets.entities() {
+            match *world.scene.hitboxes.get(bullet).unwrap() {
+                Hitbox::Oriented { scale, angle } => {
+                    assert_eq!(scale, (0.5, 0.5));
+                    angles.push(angle);
+                },
+                other => panic!("{:?} is not the configured hitbox", other),
+            }
+        }
+
+        angles.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(angles, vec![-30.0, 0.0, 30.0]);
+    }
+}